@@ -0,0 +1,18 @@
+use std::env::current_dir;
+use std::fs::create_dir_all;
+
+use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
+
+use cw1155::{Cw1155ExecuteMsg, Cw1155QueryMsg};
+use cw1155_base::msg::InstantiateMsg;
+
+fn main() {
+    let mut out_dir = current_dir().unwrap();
+    out_dir.push("schema");
+    create_dir_all(&out_dir).unwrap();
+    remove_schemas(&out_dir).unwrap();
+
+    export_schema(&schema_for!(InstantiateMsg), &out_dir);
+    export_schema(&schema_for!(Cw1155ExecuteMsg), &out_dir);
+    export_schema(&schema_for!(Cw1155QueryMsg), &out_dir);
+}
@@ -3,8 +3,17 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use deposit_cw20::msg::{Cw20DepositResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
-use deposit_cw20::state::{Cw20Deposits};
+use deposit_cw20::msg::{
+    AllowancesResponse, AllowedTokensResponse, AuctionBidsResponse, AuctionResponse,
+    AuctionsResponse, BalanceResponse, ClaimsResponse, ConfigResponse, Cw1155DepositResponse,
+    Cw1155DepositsByOwnerResponse, Cw20DepositResponse, Cw20LotsResponse, Cw721DepositAtResponse,
+    Cw721DepositChangelogResponse, Cw721DepositResponse, Cw721DepositsByOwnerResponse,
+    Cw721OperatorsResponse, DepositResponse, ExecuteMsg, InstantiateMsg, ListingsResponse,
+    LockScheduleResponse, MigrateMsg, OffersResponse, PendingRewardsResponse, QueryMsg,
+    RewardPoolsResponse, TotalCw20DepositsChangelogResponse, TotalCw20DepositsResponse,
+    TotalsResponse,
+};
+use deposit_cw20::state::{Cw20Deposits, Deposits};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -15,6 +24,33 @@ fn main() {
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
+    export_schema(&schema_for!(Deposits), &out_dir);
     export_schema(&schema_for!(Cw20Deposits), &out_dir);
+    export_schema(&schema_for!(DepositResponse), &out_dir);
     export_schema(&schema_for!(Cw20DepositResponse), &out_dir);
+    export_schema(&schema_for!(Cw20LotsResponse), &out_dir);
+    export_schema(&schema_for!(BalanceResponse), &out_dir);
+    export_schema(&schema_for!(AllowancesResponse), &out_dir);
+    export_schema(&schema_for!(ClaimsResponse), &out_dir);
+    export_schema(&schema_for!(TotalCw20DepositsResponse), &out_dir);
+    export_schema(&schema_for!(TotalCw20DepositsChangelogResponse), &out_dir);
+    export_schema(&schema_for!(Cw721DepositResponse), &out_dir);
+    export_schema(&schema_for!(Cw721DepositsByOwnerResponse), &out_dir);
+    export_schema(&schema_for!(Cw721DepositAtResponse), &out_dir);
+    export_schema(&schema_for!(Cw721DepositChangelogResponse), &out_dir);
+    export_schema(&schema_for!(Cw721OperatorsResponse), &out_dir);
+    export_schema(&schema_for!(Cw1155DepositResponse), &out_dir);
+    export_schema(&schema_for!(Cw1155DepositsByOwnerResponse), &out_dir);
+    export_schema(&schema_for!(OffersResponse), &out_dir);
+    export_schema(&schema_for!(ListingsResponse), &out_dir);
+    export_schema(&schema_for!(AuctionResponse), &out_dir);
+    export_schema(&schema_for!(AuctionsResponse), &out_dir);
+    export_schema(&schema_for!(AuctionBidsResponse), &out_dir);
+    export_schema(&schema_for!(TotalsResponse), &out_dir);
+    export_schema(&schema_for!(PendingRewardsResponse), &out_dir);
+    export_schema(&schema_for!(RewardPoolsResponse), &out_dir);
+    export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(AllowedTokensResponse), &out_dir);
+    export_schema(&schema_for!(LockScheduleResponse), &out_dir);
 }
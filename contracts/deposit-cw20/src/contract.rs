@@ -1,385 +1,4126 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coin, from_binary, to_binary, BankMsg, Binary, CustomMsg, Deps, DepsMut, Empty, Env,
-    MessageInfo, Order, Response, StdResult, Uint128, WasmMsg,
+    coin, from_binary, to_binary, Addr, BankMsg, Binary, BlockInfo, Coin, CosmosMsg, CustomMsg,
+    Decimal, Decimal256, Deps, DepsMut, Empty, Env, Event, MessageInfo, Order, OverflowError,
+    OverflowOperation, Response, StdError, StdResult, Storage, Uint128, Uint256, WasmMsg,
 };
 
-use cw2::set_contract_version;
-use cw20::{Cw20ReceiveMsg, Expiration};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use cw1155::{Cw1155BatchReceiveMsg, Cw1155ExecuteMsg};
+use cw2::{get_contract_version, set_contract_version};
+use cw20::Cw20ReceiveMsg;
 use cw20_base;
 use cw721::Cw721ReceiveMsg;
-use cw_utils;
+use cw_storage_plus::{Bound, Map};
+use cw_utils::{Duration, Expiration};
+use semver::Version;
 // use cw2::set_contract_version;
 
 use crate::error::ContractError;
+use crate::migrations;
 use crate::msg::{
-    Cw20DepositResponse, Cw20HookMsg, Cw721DepositResponse, Cw721HookMsg, DepositResponse,
-    ExecuteMsg, InstantiateMsg, QueryMsg, MigrateMsg,
+    AllowancesResponse, AllowedToken, AllowedTokensResponse, AssetKind, AuctionBidsResponse,
+    AuctionResponse, AuctionsResponse, BalanceResponse, ClaimsResponse, ConfigResponse,
+    Cw1155DepositResponse, Cw1155DepositsByOwnerResponse, Cw1155HookMsg, Cw20DepositResponse,
+    Cw20HookMsg, Cw20LockDuration, Cw20LotsResponse, Cw721DepositAtResponse,
+    Cw721DepositChangelogResponse, Cw721DepositResponse, Cw721DepositsByOwnerResponse,
+    Cw721HookMsg, Cw721OperatorsResponse, DepositResponse, ExecuteMsg, InstantiateMsg,
+    ListingsResponse, LockScheduleResponse, MigrateMsg, OffersResponse, PendingRewardsResponse,
+    QueryMsg, ReceiveMsg, RewardPoolsResponse, TotalCw20DepositsChangelogResponse,
+    TotalCw20DepositsResponse, TotalsResponse,
+};
+use crate::state::{
+    Allowance, AssetInfo, AssetTotal, Auction, AuctionBid, AuctionKind, Config, Cw1155Deposits,
+    Cw20Claim, Cw20Deposits, Cw20Lot, Cw721Deposits, Deposit, Deposits, EarlyWithdraw, Listing,
+    MarketplaceFee, Offer, PenaltyRecipient, RewardPool, RewardStake, RewardToken, TokenKind,
 };
-use crate::state::{Cw20Deposits, Cw721Deposits, Deposit, Deposits};
 use crate::traits::{DepositExecute, DepositQuery};
 
 const CONTRACT_NAME: &str = "deposit-cw20-example";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+pub(crate) const DEFAULT_LOCK_DURATION: Duration = Duration::Height(20);
+
+//lot ids start at 1, cw20 credited already unlocked shares this one
+const UNLOCKED_LOT: u64 = 0;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+//reward pools are kept for staked cw20 and for escrowed cw721 collections
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PoolKind {
+    Cw20,
+    Cw721,
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
-    _msg: InstantiateMsg,
+    info: MessageInfo,
+    msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    Ok(Response::default())
+
+    let contract = Deposit::<Empty>::default();
+    let admin = match msg.admin {
+        Some(admin) => deps.api.addr_validate(&admin)?,
+        None => info.sender,
+    };
+    let config = Config {
+        admin: admin.clone(),
+        pending_admin: None,
+        default_lock_duration: msg.default_lock_duration.unwrap_or(DEFAULT_LOCK_DURATION),
+        open: msg.open,
+        early_withdraw: None,
+        unbonding_period: None,
+        marketplace_fee: None,
+    };
+    contract.config.save(deps.storage, &config)?;
+
+    for lock in msg.cw20_lock_durations {
+        let cw20_contract = deps.api.addr_validate(&lock.contract)?;
+        contract
+            .cw20_lock_durations
+            .save(deps.storage, cw20_contract.as_str(), &lock.duration)?;
+    }
+
+    for token in msg.allowed_tokens {
+        let token_contract = deps.api.addr_validate(&token.contract)?;
+        contract
+            .allowed_tokens
+            .save(deps.storage, token_contract.as_str(), &token.kind)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("execute", "instantiate")
+        .add_attribute("admin", admin))
 }
 
-impl<'a, C> DepositExecute<C> for Deposit<'a, C>
+impl<'a, C> Deposit<'a, C>
 where
     C: CustomMsg,
 {
-    type Err = ContractError;
-    fn execute_deposit(
+    fn assert_admin(&self, storage: &dyn Storage, sender: &Addr) -> Result<Config, ContractError> {
+        let config = self.config.load(storage)?;
+        if *sender != config.admin {
+            return Err(ContractError::Unauthorized {});
+        }
+        Ok(config)
+    }
+
+    fn assert_allowed_token(
+        &self,
+        storage: &dyn Storage,
+        contract: &Addr,
+        kind: TokenKind,
+    ) -> Result<(), ContractError> {
+        if self.config.load(storage)?.open {
+            return Ok(());
+        }
+        match self.allowed_tokens.may_load(storage, contract.as_str())? {
+            Some(allowed) if allowed == kind => Ok(()),
+            _ => Err(ContractError::TokenNotAllowed {
+                contract: contract.to_string(),
+            }),
+        }
+    }
+
+    fn reward_pools_of(&self, kind: PoolKind) -> &Map<'a, (&'a str, &'a str), RewardPool> {
+        match kind {
+            PoolKind::Cw20 => &self.reward_pools,
+            PoolKind::Cw721 => &self.nft_reward_pools,
+        }
+    }
+
+    //brings a pool of target up to height, cw20 pools split reward_per_block across
+    //the staked amount while cw721 pools pay it to every escrowed nft
+    fn accrue(
+        &self,
+        storage: &dyn Storage,
+        kind: PoolKind,
+        target: &str,
+        pool: &mut RewardPool,
+        height: u64,
+    ) -> StdResult<()> {
+        let totals = match kind {
+            PoolKind::Cw20 => &self.cw20_totals,
+            PoolKind::Cw721 => &self.cw721_totals,
+        };
+        let shares = totals.may_load(storage, target)?.unwrap_or_default().amount;
+        let emission = match kind {
+            PoolKind::Cw20 => pool.reward_per_block,
+            PoolKind::Cw721 => pool.reward_per_block.checked_mul(shares)?,
+        };
+        accrue_pool(pool, shares, emission, height)
+    }
+
+    fn accrued_pools(
+        &self,
+        storage: &dyn Storage,
+        kind: PoolKind,
+        target: &str,
+        height: u64,
+    ) -> StdResult<Vec<(String, RewardPool)>> {
+        let mut pools: Vec<(String, RewardPool)> = self
+            .reward_pools_of(kind)
+            .prefix(target)
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()?;
+        for (_, pool) in pools.iter_mut() {
+            self.accrue(storage, kind, target, pool, height)?;
+        }
+        Ok(pools)
+    }
+
+    fn save_pools(
+        &self,
+        storage: &mut dyn Storage,
+        kind: PoolKind,
+        target: &str,
+        pools: &[(String, RewardPool)],
+    ) -> StdResult<()> {
+        for (key, pool) in pools {
+            self.reward_pools_of(kind)
+                .save(storage, (target, key), pool)?;
+        }
+        Ok(())
+    }
+
+    //brings every pool of staked_token up to height and settles owner's share,
+    //has to run before the owner's stake of staked_token changes
+    fn settle_rewards(
+        &self,
+        storage: &mut dyn Storage,
+        height: u64,
+        staked_token: &str,
+        owner: &str,
+    ) -> StdResult<()> {
+        let staked = self
+            .cw20_deposits
+            .may_load(storage, (owner, staked_token))?
+            .map(|deposit| deposit.amount)
+            .unwrap_or_default();
+        let pools = self.accrued_pools(storage, PoolKind::Cw20, staked_token, height)?;
+        self.save_pools(storage, PoolKind::Cw20, staked_token, &pools)?;
+        for (reward, pool) in pools {
+            let mut stake = self
+                .reward_stakes
+                .may_load(storage, (staked_token, &reward, owner))?
+                .unwrap_or_default();
+            settle_stake(&mut stake, &pool, staked)?;
+            self.reward_stakes
+                .save(storage, (staked_token, &reward, owner), &stake)?;
+        }
+        Ok(())
+    }
+
+    //each escrowed nft is one share of every pool of its collection
+    fn settled_nft_stakes(
+        &self,
+        storage: &dyn Storage,
+        collection: &str,
+        token_id: &str,
+        pools: &[(String, RewardPool)],
+    ) -> StdResult<Vec<(String, RewardToken, RewardStake)>> {
+        let mut stakes = vec![];
+        for (key, pool) in pools {
+            let mut stake = self
+                .nft_reward_stakes
+                .may_load(storage, (collection, token_id, key))?
+                .unwrap_or_default();
+            settle_stake(&mut stake, pool, Uint128::one())?;
+            stakes.push((key.clone(), pool.reward.clone(), stake));
+        }
+        Ok(stakes)
+    }
+
+    //an nft entering escrow earns from the current reward_per_share on
+    fn start_nft_rewards(
+        &self,
+        storage: &mut dyn Storage,
+        height: u64,
+        collection: &str,
+        token_id: &str,
+    ) -> StdResult<()> {
+        let pools = self.accrued_pools(storage, PoolKind::Cw721, collection, height)?;
+        self.save_pools(storage, PoolKind::Cw721, collection, &pools)?;
+        for (key, pool) in pools {
+            let stake = RewardStake {
+                reward_per_share_paid: pool.reward_per_share,
+                pending: Uint128::zero(),
+            };
+            self.nft_reward_stakes
+                .save(storage, (collection, token_id, &key), &stake)?;
+        }
+        Ok(())
+    }
+
+    //an nft leaving escrow stops earning, what it earned is handed back for payout
+    fn release_nft_rewards(
+        &self,
+        storage: &mut dyn Storage,
+        height: u64,
+        collection: &str,
+        token_id: &str,
+    ) -> StdResult<Vec<(RewardToken, Uint128)>> {
+        let pools = self.accrued_pools(storage, PoolKind::Cw721, collection, height)?;
+        self.save_pools(storage, PoolKind::Cw721, collection, &pools)?;
+        let mut payouts = vec![];
+        for (key, reward, stake) in
+            self.settled_nft_stakes(storage, collection, token_id, &pools)?
+        {
+            add_payout(&mut payouts, reward, stake.pending);
+            self.nft_reward_stakes
+                .remove(storage, (collection, token_id, &key));
+        }
+        Ok(payouts)
+    }
+
+    fn save_reward_pool(
         &self,
         deps: DepsMut,
-        info: MessageInfo,
-    ) -> Result<Response<C>, ContractError> {
-        let sender = info.sender.clone().into_string();
+        height: u64,
+        kind: PoolKind,
+        target: String,
+        reward: RewardToken,
+        reward_per_block: Uint128,
+    ) -> Result<(String, RewardToken), ContractError> {
+        let target = deps.api.addr_validate(&target)?.into_string();
+        let reward = match reward {
+            RewardToken::Cw20 { contract } => RewardToken::Cw20 {
+                contract: deps.api.addr_validate(&contract)?.into_string(),
+            },
+            native => native,
+        };
+        let pool = match self
+            .reward_pools_of(kind)
+            .may_load(deps.storage, (&target, reward.key()))?
+        {
+            Some(mut pool) => {
+                //blocks so far are paid at the old rate
+                self.accrue(deps.storage, kind, &target, &mut pool, height)?;
+                pool.reward_per_block = reward_per_block;
+                pool
+            }
+            None => RewardPool {
+                reward: reward.clone(),
+                reward_per_block,
+                balance: Uint128::zero(),
+                reward_per_share: Decimal256::zero(),
+                last_update_height: height,
+            },
+        };
+        self.reward_pools_of(kind)
+            .save(deps.storage, (&target, reward.key()), &pool)?;
+        Ok((target, reward))
+    }
 
-        let d_coins = info.funds[0].clone();
+    fn fund_reward_pool(
+        &self,
+        storage: &mut dyn Storage,
+        height: u64,
+        kind: PoolKind,
+        target: &str,
+        reward: &RewardToken,
+        amount: Uint128,
+    ) -> Result<(), ContractError> {
+        let not_found = || match kind {
+            PoolKind::Cw20 => ContractError::RewardPoolNotFound {
+                staked_token: target.to_string(),
+                reward: reward.key().to_string(),
+            },
+            PoolKind::Cw721 => ContractError::NftRewardPoolNotFound {
+                collection: target.to_string(),
+                reward: reward.key().to_string(),
+            },
+        };
+        let mut pool = self
+            .reward_pools_of(kind)
+            .may_load(storage, (target, reward.key()))?
+            .ok_or_else(not_found)?;
+        if &pool.reward != reward {
+            return Err(not_found());
+        }
+        //emit what is owed for the past blocks before the new funds can count
+        self.accrue(storage, kind, target, &mut pool, height)?;
+        pool.balance = pool.balance.checked_add(amount)?;
+        self.reward_pools_of(kind)
+            .save(storage, (target, reward.key()), &pool)?;
+        Ok(())
+    }
 
+    fn credit_native(
+        &self,
+        storage: &mut dyn Storage,
+        height: u64,
+        owner: &Addr,
+        coins: &Coin,
+    ) -> Result<(), ContractError> {
         //check to see if deposit exists
-        match self
+        let before = match self
             .deposits
-            .load(deps.storage, (&sender, d_coins.denom.as_str()))
+            .may_load(storage, (owner.as_str(), &coins.denom))?
         {
-            Ok(mut deposit) => {
+            Some(mut deposit) => {
+                let before = deposit.coins.amount;
                 //add coins to their account
-                deposit.coins.amount = deposit.coins.amount.checked_add(d_coins.amount).unwrap();
-                deposit.count = deposit.count.checked_add(1).unwrap();
+                deposit.coins.amount = deposit.coins.amount.checked_add(coins.amount)?;
+                deposit.count = deposit
+                    .count
+                    .checked_add(1)
+                    .ok_or_else(|| OverflowError::new(OverflowOperation::Add, deposit.count, 1))?;
                 self.deposits
-                    .save(deps.storage, (&sender, d_coins.denom.as_str()), &deposit)
-                    .unwrap();
+                    .save(storage, (owner.as_str(), &coins.denom), &deposit, height)?;
+                before
             }
-            Err(_) => {
+            None => {
                 //user does not exist, add them.
                 let deposit = Deposits {
                     count: 1,
-                    owner: info.sender,
-                    coins: d_coins.clone(),
+                    owner: owner.clone(),
+                    coins: coins.clone(),
                 };
                 self.deposits
-                    .save(deps.storage, (&sender, d_coins.denom.as_str()), &deposit)
-                    .unwrap();
+                    .save(storage, (owner.as_str(), &coins.denom), &deposit, height)?;
+                Uint128::zero()
             }
-        }
-        Ok(Response::new()
-            .add_attribute("execute", "deposit")
-            .add_attribute("denom", d_coins.denom)
-            .add_attribute("amount", d_coins.amount))
+        };
+        update_total(
+            storage,
+            &self.native_totals,
+            &coins.denom,
+            before,
+            before + coins.amount,
+        )?;
+        Ok(())
     }
 
-    fn execute_withdraw(
+    fn debit_native(
         &self,
-        deps: DepsMut,
-        info: MessageInfo,
-        amount: u128,
-        denom: String,
-    ) -> Result<Response<C>, ContractError> {
-        let sender = info.sender.clone().into_string();
-
+        storage: &mut dyn Storage,
+        height: u64,
+        owner: &str,
+        denom: &str,
+        amount: Uint128,
+    ) -> Result<(), ContractError> {
         let mut deposit = self
             .deposits
-            .load(deps.storage, (&sender, denom.as_str()))
-            .unwrap();
-        deposit.coins.amount = deposit
-            .coins
-            .amount
-            .checked_sub(Uint128::from(amount))
-            .unwrap();
-        deposit.count = deposit.count.checked_sub(1).unwrap();
+            .may_load(storage, (owner, denom))?
+            .ok_or_else(|| ContractError::UnknownDenom {
+                denom: denom.to_string(),
+            })?;
+        deposit.coins.amount = deposit.coins.amount.checked_sub(amount).map_err(|_| {
+            ContractError::InsufficientBalance {
+                asset: denom.to_string(),
+                available: deposit.coins.amount,
+                requested: amount,
+            }
+        })?;
+        //partial withdrawals can outnumber deposits
+        deposit.count = deposit.count.saturating_sub(1);
         self.deposits
-            .save(deps.storage, (&sender, denom.as_str()), &deposit)
-            .unwrap();
-
-        let msg = BankMsg::Send {
-            to_address: sender.clone(),
-            amount: vec![coin(amount, denom.clone())],
-        };
-
-        Ok(Response::new()
-            .add_attribute("execute", "withdraw")
-            .add_attribute("denom", denom)
-            .add_attribute("amount", amount.to_string())
-            .add_message(msg))
+            .save(storage, (owner, denom), &deposit, height)?;
+        update_total(
+            storage,
+            &self.native_totals,
+            denom,
+            deposit.coins.amount + amount,
+            deposit.coins.amount,
+        )?;
+        Ok(())
     }
 
-    fn execute_cw20_deposit(
+    //adds amount to a cw20 position as a new lot locked for lock_duration, settling rewards first
+    fn credit_cw20(
         &self,
-        deps: DepsMut,
-        env: Env,
-        info: MessageInfo,
-        owner: String,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        owner: &str,
+        contract: &str,
         amount: Uint128,
-    ) -> Result<Response<C>, ContractError> {
-        let cw20_contract_address = info.sender.clone().into_string();
-        let expiration = Expiration::AtHeight(env.block.height + 20);
-        match self
-            .cw20_deposits
-            .load(deps.storage, (&owner, &cw20_contract_address))
-        {
-            Ok(mut deposit) => {
+        lock_duration: Duration,
+    ) -> Result<u64, ContractError> {
+        self.settle_rewards(storage, block.height, contract, owner)?;
+        let expiration = lock_duration.after(block);
+        let before = match self.cw20_deposits.may_load(storage, (owner, contract))? {
+            Some(mut deposit) => {
+                let before = deposit.amount;
                 //add coins to their account
-                deposit.amount = deposit.amount.checked_add(amount).unwrap();
-                deposit.count = deposit.count.checked_add(1).unwrap();
+                deposit.amount = deposit.amount.checked_add(amount)?;
+                deposit.count = deposit
+                    .count
+                    .checked_add(1)
+                    .ok_or_else(|| OverflowError::new(OverflowOperation::Add, deposit.count, 1))?;
                 deposit.stake_time = expiration;
                 self.cw20_deposits
-                    .save(deps.storage, (&owner, &cw20_contract_address), &deposit)
-                    .unwrap();
+                    .save(storage, (owner, contract), &deposit, block.height)?;
+                before
             }
-            Err(_) => {
+            None => {
                 //user does not exist, add them.
                 let deposit = Cw20Deposits {
                     count: 1,
-                    owner: owner.clone(),
-                    contract: info.sender.into_string(),
+                    owner: owner.to_string(),
+                    contract: contract.to_string(),
                     amount,
                     stake_time: expiration,
                 };
                 self.cw20_deposits
-                    .save(deps.storage, (&owner, &cw20_contract_address), &deposit)
-                    .unwrap();
+                    .save(storage, (owner, contract), &deposit, block.height)?;
+                Uint128::zero()
             }
-        }
+        };
+        update_total(
+            storage,
+            &self.cw20_totals,
+            contract,
+            before,
+            before + amount,
+        )?;
 
-        self.total_cw20_deposits.update(
-            deps.storage,
-            env.block.height,
-            |total| -> StdResult<u64> { Ok(total.unwrap_or_default().checked_add(1u64).unwrap()) },
+        //every locked deposit is its own lot so it unlocks independently of older ones.
+        //unlocked amounts are merged so incoming transfers cannot pile up lots
+        let lot_id = if expiration.is_expired(block) {
+            UNLOCKED_LOT
+        } else {
+            let lot_id = self.cw20_lot_seq.may_load(storage)?.unwrap_or_default() + 1;
+            self.cw20_lot_seq.save(storage, &lot_id)?;
+            lot_id
+        };
+        self.cw20_lots.update(
+            storage,
+            (owner, contract, lot_id),
+            |lot| -> StdResult<Cw20Lot> {
+                Ok(match lot {
+                    Some(mut lot) => {
+                        lot.amount = lot.amount.checked_add(amount)?;
+                        lot
+                    }
+                    None => Cw20Lot {
+                        amount,
+                        stake_time: expiration,
+                        lock_duration,
+                    },
+                })
+            },
         )?;
 
-        Ok(Response::new()
-            .add_attribute("execute", "cw20_deposit")
-            .add_attribute("owner", owner)
-            .add_attribute("contract", cw20_contract_address.to_string())
-            .add_attribute("amount", amount.to_string()))
+        self.total_cw20_deposits.update(
+            storage,
+            block.height,
+            |total| -> Result<u64, ContractError> {
+                let total = total.unwrap_or_default();
+                Ok(total
+                    .checked_add(1u64)
+                    .ok_or_else(|| OverflowError::new(OverflowOperation::Add, total, 1))?)
+            },
+        )?;
+        Ok(lot_id)
     }
 
-    fn execute_cw20_withdraw(
+    //takes amount out of a cw20 position, matured lots first and oldest first.
+    //locked lots are only touched with early set, the penalty on them is returned.
+    //rewards are settled first since they change with the stake
+    fn withdraw_cw20(
         &self,
-        deps: DepsMut,
-        env: Env,
-        info: MessageInfo,
-        contract: String,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        owner: &str,
+        contract: &str,
         amount: Uint128,
-    ) -> Result<Response<C>, ContractError> {
-        let sender = info.sender.clone().into_string();
-        match self.cw20_deposits.load(deps.storage, (&sender, &contract)) {
-            Ok(mut deposit) => {
-                if deposit.stake_time.is_expired(&env.block) == false {
-                    return Err(ContractError::StakeDurationNotPassed {});
-                }
-
-                deposit.amount = deposit.amount.checked_sub(amount).unwrap();
-                deposit.count = deposit.count.checked_sub(1).unwrap();
-                self.cw20_deposits
-                    .save(deps.storage, (&sender, &contract), &deposit)
-                    .unwrap();
-
-                let exe_msg = cw20_base::msg::ExecuteMsg::Transfer {
-                    recipient: sender,
-                    amount: Uint128::from(amount),
-                };
-                let msg = WasmMsg::Execute {
-                    contract_addr: contract,
-                    msg: to_binary(&exe_msg)?,
-                    funds: vec![],
-                };
+        early: Option<&EarlyWithdraw>,
+    ) -> Result<Uint128, ContractError> {
+        self.settle_rewards(storage, block.height, contract, owner)?;
+        let mut deposit = self
+            .cw20_deposits
+            .may_load(storage, (owner, contract))?
+            .ok_or_else(|| ContractError::UnknownCw20Position {
+                owner: owner.to_string(),
+                contract: contract.to_string(),
+            })?;
+        deposit.amount =
+            deposit
+                .amount
+                .checked_sub(amount)
+                .map_err(|_| ContractError::InsufficientBalance {
+                    asset: contract.to_string(),
+                    available: deposit.amount,
+                    requested: amount,
+                })?;
 
-                self.total_cw20_deposits.update(
-                    deps.storage,
-                    env.block.height,
-                    |total| -> StdResult<u64> { Ok(total.unwrap_or_default().checked_sub(1u64).unwrap()) },
-                )?;
+        //lots are read until the matured ones cover amount, locked ones are only kept
+        //for an early withdraw in case the matured ones fall short
+        let mut matured = vec![];
+        let mut locked = vec![];
+        let mut covered = Uint128::zero();
+        for item in
+            self.cw20_lots
+                .prefix((owner, contract))
+                .range(storage, None, None, Order::Ascending)
+        {
+            let (lot_id, lot) = item?;
+            if lot.stake_time.is_expired(block) {
+                covered += lot.amount;
+                matured.push((lot_id, lot));
+                if covered >= amount {
+                    break;
+                }
+            } else if early.is_some() {
+                locked.push((lot_id, lot));
+            }
+        }
 
-                Ok(Response::new()
-                    .add_attribute("execute", "withdraw")
-                    .add_message(msg))
+        let mut remaining = amount;
+        let mut penalty = Uint128::zero();
+        for (lot_id, mut lot) in matured.into_iter().chain(locked) {
+            if remaining.is_zero() {
+                break;
+            }
+            let taken = remaining.min(lot.amount);
+            remaining -= taken;
+            if let Some(early) = early {
+                penalty += lot_penalty(&lot, taken, early.max_penalty, block);
             }
-            Err(_) => {
-                return Err(ContractError::NoCw20ToWithdraw {});
+            lot.amount -= taken;
+            if lot.amount.is_zero() {
+                self.cw20_lots.remove(storage, (owner, contract, lot_id));
+            } else {
+                self.cw20_lots
+                    .save(storage, (owner, contract, lot_id), &lot)?;
             }
         }
+        if !remaining.is_zero() {
+            return Err(ContractError::StakeDurationNotPassed {});
+        }
+        //partial withdrawals can outnumber deposits
+        deposit.count = deposit.count.saturating_sub(1);
+        self.cw20_deposits
+            .save(storage, (owner, contract), &deposit, block.height)?;
+        update_total(
+            storage,
+            &self.cw20_totals,
+            contract,
+            deposit.amount + amount,
+            deposit.amount,
+        )?;
+        self.total_cw20_deposits
+            .update(storage, block.height, |total| -> StdResult<u64> {
+                Ok(total.unwrap_or_default().saturating_sub(1u64))
+            })?;
+        Ok(penalty)
     }
 
-    fn execute_cw721_deposit(
+    //the penalty goes to the remaining stakers through the pool paying out the staked cw20 itself.
+    //settle_rewards already accrued every pool of staked_token to this height.
+    //false when nobody is left staking to share it with
+    fn share_penalty(
         &self,
-        deps: DepsMut,
-        env: Env,
-        info: MessageInfo,
-        owner: String,
-        token_id: String,
-    ) -> Result<Response<C>, ContractError> {
-        let cw721_contract_address = info.sender.clone().into_string();
+        storage: &mut dyn Storage,
+        height: u64,
+        staked_token: &str,
+        amount: Uint128,
+    ) -> StdResult<bool> {
+        let staked = self
+            .cw20_totals
+            .may_load(storage, staked_token)?
+            .unwrap_or_default()
+            .amount;
+        if staked.is_zero() {
+            return Ok(false);
+        }
+        let mut pool = self
+            .reward_pools
+            .may_load(storage, (staked_token, staked_token))?
+            .unwrap_or_else(|| RewardPool {
+                reward: RewardToken::Cw20 {
+                    contract: staked_token.to_string(),
+                },
+                reward_per_block: Uint128::zero(),
+                balance: Uint128::zero(),
+                reward_per_share: Decimal256::zero(),
+                last_update_height: height,
+            });
+        pool.reward_per_share = pool
+            .reward_per_share
+            .checked_add(Decimal256::from_ratio(amount, staked))?;
+        self.reward_pools
+            .save(storage, (staked_token, staked_token), &pool)?;
+        Ok(true)
+    }
 
+    //queues cw20 leaving custody until release_at, returns the claim id
+    fn queue_cw20_claim(
+        &self,
+        storage: &mut dyn Storage,
+        owner: &str,
+        contract: &str,
+        amount: Uint128,
+        release_at: Expiration,
+    ) -> StdResult<u64> {
+        let claim_id = self.cw20_claim_seq.may_load(storage)?.unwrap_or_default() + 1;
+        self.cw20_claim_seq.save(storage, &claim_id)?;
+        self.cw20_claims.save(
+            storage,
+            (owner, claim_id),
+            &Cw20Claim {
+                contract: contract.to_string(),
+                amount,
+                release_at,
+            },
+        )?;
+        Ok(claim_id)
+    }
+
+    fn credit_cw721(
+        &self,
+        storage: &mut dyn Storage,
+        height: u64,
+        owner: &str,
+        contract: &str,
+        token_id: &str,
+    ) -> Result<(), ContractError> {
+        self.start_nft_rewards(storage, height, contract, token_id)?;
         let data = Cw721Deposits {
-            owner: owner.clone(),
-            contract: info.sender.into_string(),
-            token_id: token_id.clone(),
+            owner: owner.to_string(),
+            contract: contract.to_string(),
+            token_id: token_id.to_string(),
         };
         self.cw721_deposits
-            .save(
-                deps.storage,
-                (&cw721_contract_address, &token_id),
-                &data,
-                env.block.height,
-            )
-            .unwrap();
-
-        Ok(Response::new()
-            .add_attribute("execute", "cw721_deposit")
-            .add_attribute("owner", owner)
-            .add_attribute("contract", cw721_contract_address.to_string()))
+            .save(storage, (contract, token_id), &data, height)?;
+        self.cw721_custody
+            .save(storage, (owner, contract, token_id), &Empty {})?;
+        let held = self.cw721_held(storage, owner, contract)?;
+        update_total(
+            storage,
+            &self.cw721_totals,
+            contract,
+            held - Uint128::one(),
+            held,
+        )?;
+        Ok(())
     }
 
-    fn execute_cw721_withdraw(
+    //takes an nft out of owner's escrow, what it earned is handed back for payout
+    fn debit_cw721(
         &self,
-        deps: DepsMut,
-        env: Env,
-        info: MessageInfo,
-        contract: String,
-        token_id: String,
-    ) -> Result<Response<C>, ContractError> {
-        let owner = info.sender.clone().into_string();
-
-        let _deposit = self
+        storage: &mut dyn Storage,
+        height: u64,
+        owner: &str,
+        contract: &str,
+        token_id: &str,
+    ) -> Result<Vec<(RewardToken, Uint128)>, ContractError> {
+        let deposit = self
             .cw721_deposits
-            .load(deps.storage, (&contract, &token_id))
-            .unwrap();
+            .may_load(storage, (contract, token_id))?
+            .ok_or(ContractError::NoCw721ToWithdraw {})?;
+        if deposit.owner != owner {
+            return Err(ContractError::InvalidOwner {});
+        }
+        //auctioned nfts only leave the seller through settlement
+        if self.auctions.has(storage, (contract, token_id)) {
+            return Err(ContractError::NftInAuction {
+                contract: contract.to_string(),
+                token_id: token_id.to_string(),
+            });
+        }
+        let payouts = self.release_nft_rewards(storage, height, contract, token_id)?;
+        //a listing is only good while the seller holds the nft
+        self.listings.remove(storage, (contract, token_id));
 
+        //removing at this height keeps the custody record in the changelog
         self.cw721_deposits
-            .remove(deps.storage, (&contract, &token_id), env.block.height)
-            .unwrap();
-
-        let exe_msg = nft::contract::ExecuteMsg::TransferNft {
-            recipient: owner,
-            token_id: token_id,
-        };
-        let msg = WasmMsg::Execute {
-            contract_addr: contract,
-            msg: to_binary(&exe_msg)?,
-            funds: vec![],
-        };
+            .remove(storage, (contract, token_id), height)?;
+        let held = self.cw721_held(storage, owner, contract)?;
+        update_total(
+            storage,
+            &self.cw721_totals,
+            contract,
+            held + Uint128::one(),
+            held,
+        )?;
+        Ok(payouts)
+    }
 
-        Ok(Response::new()
-            .add_attribute("execute", "cw721_withdraw")
-            .add_message(msg))
+    fn credit_cw1155(
+        &self,
+        storage: &mut dyn Storage,
+        owner: &str,
+        contract: &str,
+        token_id: &str,
+        amount: Uint128,
+    ) -> Result<(), ContractError> {
+        let mut deposit = self
+            .cw1155_deposits
+            .may_load(storage, (contract, token_id, owner))?
+            .unwrap_or(Cw1155Deposits {
+                owner: owner.to_string(),
+                contract: contract.to_string(),
+                token_id: token_id.to_string(),
+                amount: Uint128::zero(),
+            });
+        let before = deposit.amount;
+        deposit.amount = deposit.amount.checked_add(amount)?;
+        self.cw1155_deposits
+            .save(storage, (contract, token_id, owner), &deposit)?;
+        update_total(
+            storage,
+            &self.cw1155_totals,
+            &format!("{}/{}", contract, token_id),
+            before,
+            deposit.amount,
+        )?;
+        Ok(())
     }
-}
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn execute(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    msg: ExecuteMsg,
-) -> Result<Response, ContractError> {
-    let contract = Deposit::<Empty>::default();
-    match msg {
-        ExecuteMsg::Deposit {} => contract.execute_deposit(deps, info),
-        ExecuteMsg::Withdraw { amount, denom } => {
-            contract.execute_withdraw(deps, info, amount, denom)
-        }
-        ExecuteMsg::Receive(cw20_msg) => receive_cw20(deps, env, info, &contract, cw20_msg),
-        ExecuteMsg::ReceiveNft(cw721_msg) => receive_cw721(deps, env, info, &contract, cw721_msg),
-        ExecuteMsg::WithdrawCw20 { address, amount } => {
-            contract.execute_cw20_withdraw(deps, env, info, address, amount)
+    //an emptied balance is removed so it drops out of the listings
+    fn debit_cw1155(
+        &self,
+        storage: &mut dyn Storage,
+        owner: &str,
+        contract: &str,
+        token_id: &str,
+        amount: Uint128,
+    ) -> Result<(), ContractError> {
+        let mut deposit = self
+            .cw1155_deposits
+            .may_load(storage, (contract, token_id, owner))?
+            .ok_or_else(|| ContractError::UnknownCw1155Balance {
+                owner: owner.to_string(),
+                contract: contract.to_string(),
+                token_id: token_id.to_string(),
+            })?;
+        let before = deposit.amount;
+        deposit.amount =
+            deposit
+                .amount
+                .checked_sub(amount)
+                .map_err(|_| ContractError::InsufficientBalance {
+                    asset: format!("{}/{}", contract, token_id),
+                    available: before,
+                    requested: amount,
+                })?;
+        if deposit.amount.is_zero() {
+            self.cw1155_deposits
+                .remove(storage, (contract, token_id, owner))?;
+        } else {
+            self.cw1155_deposits
+                .save(storage, (contract, token_id, owner), &deposit)?;
         }
-        ExecuteMsg::WithdrawNft {
-            contract_addr,
-            token_id,
-        } => contract.execute_cw721_withdraw(deps, env, info, contract_addr, token_id),
+        update_total(
+            storage,
+            &self.cw1155_totals,
+            &format!("{}/{}", contract, token_id),
+            before,
+            deposit.amount,
+        )?;
+        Ok(())
     }
-}
 
-impl<'a, C> DepositQuery for Deposit<'a, C>
-where
-    C: CustomMsg,
-{
-    fn query_deposits(&self, deps: Deps, address: String) -> StdResult<DepositResponse> {
-        let res: StdResult<Vec<_>> = self
-            .deposits
-            .prefix(&address)
-            .range(deps.storage, None, None, Order::Ascending)
-            .collect();
-        let deposits = res?;
-        Ok(DepositResponse { deposits })
+    //lock_duration only applies to cw20, which lands as a new lot
+    fn credit_asset(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        owner: &str,
+        asset: &AssetInfo,
+        amount: Uint128,
+        lock_duration: Duration,
+    ) -> Result<(), ContractError> {
+        match asset {
+            AssetInfo::Native { denom } => self.credit_native(
+                storage,
+                block.height,
+                &Addr::unchecked(owner),
+                &Coin {
+                    denom: denom.clone(),
+                    amount,
+                },
+            ),
+            AssetInfo::Cw20 { contract } => self
+                .credit_cw20(storage, block, owner, contract, amount, lock_duration)
+                .map(|_| ()),
+            AssetInfo::Cw721 { contract, token_id } => {
+                assert_single_nft(amount)?;
+                self.credit_cw721(storage, block.height, owner, contract, token_id)
+            }
+            AssetInfo::Cw1155 { contract, token_id } => {
+                self.credit_cw1155(storage, owner, contract, token_id, amount)
+            }
+        }
     }
 
-    fn query_cw20_deposits(&self, deps: Deps, address: String) -> StdResult<Cw20DepositResponse> {
-        let res: StdResult<Vec<_>> = self
-            .cw20_deposits
-            .prefix(&address)
-            .range(deps.storage, None, None, Order::Ascending)
-            .collect();
-        let deposits = res?;
-        Ok(Cw20DepositResponse { deposits })
+    //cw20 only comes out of unlocked lots. rewards an nft earned in escrow are
+    //returned as payouts to owner
+    fn debit_asset(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        owner: &str,
+        asset: &AssetInfo,
+        amount: Uint128,
+    ) -> Result<Vec<CosmosMsg<C>>, ContractError> {
+        //nothing to send, an empty BankMsg::Send would fail the whole tx later
+        if amount.is_zero() {
+            return Err(ContractError::ZeroAmount {});
+        }
+        match asset {
+            AssetInfo::Native { denom } => {
+                self.debit_native(storage, block.height, owner, denom, amount)?;
+                Ok(vec![])
+            }
+            AssetInfo::Cw20 { contract } => {
+                self.withdraw_cw20(storage, block, owner, contract, amount, None)?;
+                Ok(vec![])
+            }
+            AssetInfo::Cw721 { contract, token_id } => {
+                assert_single_nft(amount)?;
+                let payouts = self.debit_cw721(storage, block.height, owner, contract, token_id)?;
+                let mut msgs = vec![];
+                for (reward, amount) in payouts {
+                    msgs.push(reward_msg(reward, owner, amount)?);
+                }
+                Ok(msgs)
+            }
+            AssetInfo::Cw1155 { contract, token_id } => {
+                self.debit_cw1155(storage, owner, contract, token_id, amount)?;
+                Ok(vec![])
+            }
+        }
     }
 
-    fn query_cw721_by_contract(
+    fn load_listing(
         &self,
-        deps: Deps,
-        contract: String,
-    ) -> StdResult<Cw721DepositResponse> {
-        let res: StdResult<Vec<_>> = self
-            .cw721_deposits
-            .prefix(&contract)
-            .range(deps.storage, None, None, Order::Ascending)
-            .collect();
-        let deposits = res?;
-        Ok(Cw721DepositResponse { deposits })
+        storage: &dyn Storage,
+        contract: &str,
+        token_id: &str,
+    ) -> Result<Listing, ContractError> {
+        self.listings
+            .may_load(storage, (contract, token_id))?
+            .ok_or_else(|| ContractError::ListingNotFound {
+                contract: contract.to_string(),
+                token_id: token_id.to_string(),
+            })
     }
 
-    fn query_cw721_by_owner(&self, deps: Deps, address: String) -> StdResult<Cw721DepositResponse> {
-        let res: StdResult<Vec<_>> = self
-            .cw721_deposits
-            .idx
-            .owner
-            .prefix(address)
-            .range(deps.storage, None, None, Order::Ascending)
-            .collect();
-        let deposits = res?;
-        Ok(Cw721DepositResponse { deposits })
+    fn load_auction(
+        &self,
+        storage: &dyn Storage,
+        contract: &str,
+        token_id: &str,
+    ) -> Result<Auction, ContractError> {
+        self.auctions
+            .may_load(storage, (contract, token_id))?
+            .ok_or_else(|| ContractError::AuctionNotFound {
+                contract: contract.to_string(),
+                token_id: token_id.to_string(),
+            })
     }
 
-    fn query_total_cw20_deposits_changelog(&self, deps: Deps) -> StdResult<Vec<(u64, Option<u64>)>>{
-        let res: StdResult<Vec<_>> = self
-            .total_cw20_deposits.changelog().range(deps.storage, None, None, Order::Ascending).collect();
-        let changelog = res?;
-        Ok( changelog.into_iter().map(|(k, v)| (k, v.old)).collect() )
+    //nfts are sold for a native denom or an allowed cw20
+    fn assert_payment_asset(&self, deps: Deps, payment: &AssetInfo) -> Result<(), ContractError> {
+        match payment {
+            AssetInfo::Native { .. } => Ok(()),
+            AssetInfo::Cw20 { contract } => {
+                let cw20 = deps.api.addr_validate(contract)?;
+                self.assert_allowed_token(deps.storage, &cw20, TokenKind::Cw20)
+            }
+            _ => Err(ContractError::InvalidPaymentAsset {}),
+        }
     }
-}
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    let contract = Deposit::<Empty>::default();
-    match msg {
-        QueryMsg::Deposits { address } => to_binary(&contract.query_deposits(deps, address)?),
-        QueryMsg::Cw20Deposits { address } => {
-            to_binary(&contract.query_cw20_deposits(deps, address)?)
+    //seller has to hold the nft here and not have it in an auction already
+    fn assert_nft_for_sale(
+        &self,
+        storage: &dyn Storage,
+        contract: &str,
+        token_id: &str,
+        seller: &str,
+    ) -> Result<(), ContractError> {
+        let deposit = self
+            .cw721_deposits
+            .may_load(storage, (contract, token_id))?
+            .ok_or_else(|| ContractError::NftNotDeposited {
+                contract: contract.to_string(),
+                token_id: token_id.to_string(),
+            })?;
+        if deposit.owner != seller {
+            return Err(ContractError::InvalidOwner {});
         }
+        if self.auctions.has(storage, (contract, token_id)) {
+            return Err(ContractError::NftInAuction {
+                contract: contract.to_string(),
+                token_id: token_id.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    //removes the auction and, if it got a bid, hands the nft to the highest
+    //bidder and the bid to the seller
+    fn close_auction(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        auction: &Auction,
+    ) -> Result<Vec<CosmosMsg<C>>, ContractError> {
+        self.auctions
+            .remove(storage, (&auction.contract, &auction.token_id));
+        let bid = match &auction.highest_bid {
+            Some(bid) => bid,
+            None => return Ok(vec![]),
+        };
+
+        let asset = AssetInfo::Cw721 {
+            contract: auction.contract.clone(),
+            token_id: auction.token_id.clone(),
+        };
+        let mut msgs = self.sale_msgs(
+            storage,
+            &env.contract.address,
+            &auction.payment,
+            bid.amount,
+            &auction.seller,
+        )?;
+        msgs.extend(self.debit_asset(
+            storage,
+            &env.block,
+            &auction.seller,
+            &asset,
+            Uint128::one(),
+        )?);
+        self.credit_asset(
+            storage,
+            &env.block,
+            &bid.bidder,
+            &asset,
+            Uint128::one(),
+            Duration::Height(0),
+        )?;
+        Ok(msgs)
+    }
+
+    //pays price of an nft sale to seller, less the marketplace fee which goes to its collector
+    fn sale_msgs(
+        &self,
+        storage: &dyn Storage,
+        holder: &Addr,
+        payment: &AssetInfo,
+        price: Uint128,
+        seller: &str,
+    ) -> Result<Vec<CosmosMsg<C>>, ContractError> {
+        let (fee, collector) = match self.config.load(storage)?.marketplace_fee {
+            Some(fee) => (price * fee.rate, fee.collector),
+            None => (Uint128::zero(), String::new()),
+        };
+        //zero amounts are left out, the bank rejects empty sends
+        let mut msgs = vec![];
+        if fee < price {
+            msgs.push(asset_msg(holder, payment, price - fee, seller, None)?);
+        }
+        if !fee.is_zero() {
+            msgs.push(asset_msg(holder, payment, fee, &collector, None)?);
+        }
+        Ok(msgs)
+    }
+
+    //with an unbonding period cw20 can only leave through UnbondCw20
+    fn assert_withdrawable(
+        &self,
+        storage: &dyn Storage,
+        asset: &AssetInfo,
+    ) -> Result<(), ContractError> {
+        if let AssetInfo::Cw20 { .. } = asset {
+            if self.config.load(storage)?.unbonding_period.is_some() {
+                return Err(ContractError::UnbondingRequired {});
+            }
+        }
+        Ok(())
+    }
+
+    //number of nfts of a collection escrowed for owner, counted up to two as
+    //the totals only need to tell none, one and more apart
+    fn cw721_held(&self, storage: &dyn Storage, owner: &str, contract: &str) -> StdResult<Uint128> {
+        let collections: Vec<String> = self
+            .cw721_deposits
+            .idx
+            .owner
+            .prefix(owner.to_string())
+            .keys(
+                storage,
+                Some(Bound::inclusive((contract, ""))),
+                None,
+                Order::Ascending,
+            )
+            .take(2)
+            .map(|key| key.map(|(collection, _)| collection))
+            .collect::<StdResult<_>>()?;
+        let held = collections
+            .iter()
+            .filter(|c| c.as_str() == contract)
+            .count();
+        Ok(Uint128::from(held as u128))
+    }
+}
+
+impl<'a, C> DepositExecute<C> for Deposit<'a, C>
+where
+    C: CustomMsg,
+{
+    type Err = ContractError;
+    fn execute_deposit(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response<C>, ContractError> {
+        let sender = info.sender.clone().into_string();
+
+        if info.funds.is_empty() {
+            return Err(ContractError::InvalidCoin {});
+        }
+
+        //reject zero amounts and denoms sent more than once before crediting anything
+        let mut denoms: Vec<&str> = Vec::with_capacity(info.funds.len());
+        for d_coins in info.funds.iter() {
+            if d_coins.amount.is_zero() || denoms.contains(&d_coins.denom.as_str()) {
+                return Err(ContractError::InvalidCoin {});
+            }
+            denoms.push(d_coins.denom.as_str());
+        }
+
+        let mut res = Response::new().add_attribute("execute", "deposit");
+        for d_coins in info.funds.iter() {
+            let asset = AssetInfo::Native {
+                denom: d_coins.denom.clone(),
+            };
+            self.credit_asset(
+                deps.storage,
+                &env.block,
+                &sender,
+                &asset,
+                d_coins.amount,
+                Duration::Height(0),
+            )?;
+            res = res.add_event(
+                Event::new("deposit")
+                    .add_attribute("owner", sender.clone())
+                    .add_attribute("denom", d_coins.denom.clone())
+                    .add_attribute("amount", d_coins.amount),
+            );
+        }
+        Ok(res)
+    }
+
+    fn execute_withdraw(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        asset: AssetInfo,
+        amount: Uint128,
+        recipient: Option<String>,
+        msg: Option<Binary>,
+    ) -> Result<Response<C>, ContractError> {
+        let sender = info.sender.into_string();
+        let recipient = match recipient {
+            Some(recipient) => deps.api.addr_validate(&recipient)?.into_string(),
+            None => sender.clone(),
+        };
+        self.assert_withdrawable(deps.storage, &asset)?;
+
+        //an nft leaves the escrow of its depositor, who may have approved the sender as operator
+        let owner = match &asset {
+            AssetInfo::Cw721 { contract, token_id } => {
+                let deposit = self
+                    .cw721_deposits
+                    .may_load(deps.storage, (contract, token_id))?
+                    .ok_or(ContractError::NoCw721ToWithdraw {})?;
+                if deposit.owner != sender {
+                    match self
+                        .cw721_operators
+                        .may_load(deps.storage, (&deposit.owner, &sender))?
+                    {
+                        Some(expires) if !expires.is_expired(&env.block) => {}
+                        _ => return Err(ContractError::InvalidOwner {}),
+                    }
+                }
+                deposit.owner
+            }
+            _ => sender,
+        };
+        let reward_msgs = self.debit_asset(deps.storage, &env.block, &owner, &asset, amount)?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "withdraw")
+            .add_attribute("owner", owner)
+            .add_attribute("recipient", recipient.clone())
+            .add_attribute("asset", asset.key())
+            .add_attribute("amount", amount.to_string())
+            .add_message(asset_msg(
+                &env.contract.address,
+                &asset,
+                amount,
+                &recipient,
+                msg,
+            )?)
+            .add_messages(reward_msgs))
+    }
+
+    fn execute_cw20_deposit(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        owner: String,
+        amount: Uint128,
+    ) -> Result<Response<C>, ContractError> {
+        let cw20_contract_address = info.sender.into_string();
+        let lock_duration = match self
+            .cw20_lock_durations
+            .may_load(deps.storage, &cw20_contract_address)?
+        {
+            Some(duration) => duration,
+            None => self.config.load(deps.storage)?.default_lock_duration,
+        };
+        let lot_id = self.credit_cw20(
+            deps.storage,
+            &env.block,
+            &owner,
+            &cw20_contract_address,
+            amount,
+            lock_duration,
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "cw20_deposit")
+            .add_attribute("owner", owner)
+            .add_attribute("contract", cw20_contract_address)
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("lot_id", lot_id.to_string()))
+    }
+
+    fn execute_transfer_deposit(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        recipient: String,
+        asset: AssetInfo,
+        amount: Uint128,
+    ) -> Result<Response<C>, ContractError> {
+        if amount.is_zero() {
+            return Err(ContractError::ZeroAmount {});
+        }
+        let recipient = deps.api.addr_validate(&recipient)?.into_string();
+        let sender = info.sender.into_string();
+        //only unlocked cw20 lots move, they reach the recipient unlocked
+        let reward_msgs = self.debit_asset(deps.storage, &env.block, &sender, &asset, amount)?;
+        self.credit_asset(
+            deps.storage,
+            &env.block,
+            &recipient,
+            &asset,
+            amount,
+            Duration::Height(0),
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "transfer_deposit")
+            .add_attribute("recipient", recipient)
+            .add_attribute("asset", asset.key())
+            .add_attribute("amount", amount.to_string())
+            .add_messages(reward_msgs))
+    }
+
+    fn execute_increase_allowance(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        spender: String,
+        asset: AssetInfo,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    ) -> Result<Response<C>, ContractError> {
+        let spender = deps.api.addr_validate(&spender)?.into_string();
+        let owner = info.sender.into_string();
+        if spender == owner {
+            return Err(ContractError::CannotSetOwnAccount {});
+        }
+        let expires = expires.unwrap_or_default();
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::InvalidExpiration {});
+        }
+        let key = asset.key();
+        let allowance = match self
+            .allowances
+            .may_load(deps.storage, (&owner, &spender, &key))?
+        {
+            //an expired allowance is started over
+            Some(allowance) if !allowance.expires.is_expired(&env.block) => Allowance {
+                amount: allowance.amount.checked_add(amount)?,
+                expires,
+                asset,
+            },
+            _ => Allowance {
+                amount,
+                expires,
+                asset,
+            },
+        };
+        self.allowances
+            .save(deps.storage, (&owner, &spender, &key), &allowance)?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "increase_allowance")
+            .add_attribute("spender", spender)
+            .add_attribute("asset", key)
+            .add_attribute("amount", allowance.amount.to_string()))
+    }
+
+    fn execute_decrease_allowance(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        spender: String,
+        asset: AssetInfo,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    ) -> Result<Response<C>, ContractError> {
+        let spender = deps.api.addr_validate(&spender)?.into_string();
+        let owner = info.sender.into_string();
+        if spender == owner {
+            return Err(ContractError::CannotSetOwnAccount {});
+        }
+        let mut allowance = self
+            .allowances
+            .may_load(deps.storage, (&owner, &spender, &asset.key()))?
+            .ok_or(ContractError::NoAllowance {})?;
+        allowance.amount = allowance.amount.saturating_sub(amount);
+        if allowance.amount.is_zero() {
+            self.allowances
+                .remove(deps.storage, (&owner, &spender, &asset.key()));
+        } else {
+            if let Some(expires) = expires {
+                if expires.is_expired(&env.block) {
+                    return Err(ContractError::InvalidExpiration {});
+                }
+                allowance.expires = expires;
+            }
+            self.allowances
+                .save(deps.storage, (&owner, &spender, &asset.key()), &allowance)?;
+        }
+
+        Ok(Response::new()
+            .add_attribute("execute", "decrease_allowance")
+            .add_attribute("spender", spender)
+            .add_attribute("asset", asset.key())
+            .add_attribute("amount", allowance.amount.to_string()))
+    }
+
+    fn execute_withdraw_from(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        owner: String,
+        asset: AssetInfo,
+        amount: Uint128,
+        recipient: String,
+    ) -> Result<Response<C>, ContractError> {
+        let recipient = deps.api.addr_validate(&recipient)?.into_string();
+        let spender = info.sender.into_string();
+        let mut allowance = self
+            .allowances
+            .may_load(deps.storage, (&owner, &spender, &asset.key()))?
+            .ok_or(ContractError::NoAllowance {})?;
+        if allowance.expires.is_expired(&env.block) {
+            return Err(ContractError::AllowanceExpired {});
+        }
+        allowance.amount = allowance.amount.checked_sub(amount).map_err(|_| {
+            ContractError::InsufficientAllowance {
+                available: allowance.amount,
+                requested: amount,
+            }
+        })?;
+        if allowance.amount.is_zero() {
+            self.allowances
+                .remove(deps.storage, (&owner, &spender, &asset.key()));
+        } else {
+            self.allowances
+                .save(deps.storage, (&owner, &spender, &asset.key()), &allowance)?;
+        }
+
+        self.assert_withdrawable(deps.storage, &asset)?;
+        let reward_msgs = self.debit_asset(deps.storage, &env.block, &owner, &asset, amount)?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "withdraw_from")
+            .add_attribute("owner", owner)
+            .add_attribute("recipient", recipient.clone())
+            .add_attribute("asset", asset.key())
+            .add_attribute("amount", amount.to_string())
+            .add_message(asset_msg(
+                &env.contract.address,
+                &asset,
+                amount,
+                &recipient,
+                None,
+            )?)
+            .add_messages(reward_msgs))
+    }
+
+    fn execute_cw20_unbond(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract: String,
+        amount: Uint128,
+    ) -> Result<Response<C>, ContractError> {
+        if amount.is_zero() {
+            return Err(ContractError::ZeroAmount {});
+        }
+        let unbonding_period = self.config.load(deps.storage)?.unbonding_period;
+        let sender = info.sender.into_string();
+        //unbonding cw20 stops earning rewards right away
+        self.withdraw_cw20(deps.storage, &env.block, &sender, &contract, amount, None)?;
+
+        //without a period the claim is released immediately
+        let release_at = unbonding_period
+            .unwrap_or(Duration::Height(0))
+            .after(&env.block);
+        let claim_id =
+            self.queue_cw20_claim(deps.storage, &sender, &contract, amount, release_at)?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "cw20_unbond")
+            .add_attribute("contract", contract)
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("claim_id", claim_id.to_string())
+            .add_attribute("release_at", release_at.to_string()))
+    }
+
+    fn execute_claim_unbonded(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response<C>, ContractError> {
+        let sender = info.sender.into_string();
+        let released: Vec<(u64, Cw20Claim)> = self
+            .cw20_claims
+            .prefix(&sender)
+            .range(deps.storage, None, None, Order::Ascending)
+            .filter(|item| match item {
+                Ok((_, claim)) => claim.release_at.is_expired(&env.block),
+                Err(_) => true,
+            })
+            .collect::<StdResult<_>>()?;
+        if released.is_empty() {
+            return Err(ContractError::NoMaturedClaims {});
+        }
+
+        //a single transfer per cw20
+        let mut payouts: BTreeMap<String, Uint128> = BTreeMap::new();
+        for (claim_id, claim) in released {
+            self.cw20_claims.remove(deps.storage, (&sender, claim_id));
+            *payouts.entry(claim.contract).or_default() += claim.amount;
+        }
+        //cw20 rejects empty transfers
+        let msgs = payouts
+            .into_iter()
+            .filter(|(_, amount)| !amount.is_zero())
+            .map(|(contract, amount)| -> StdResult<WasmMsg> {
+                Ok(WasmMsg::Execute {
+                    contract_addr: contract,
+                    msg: to_binary(&cw20_base::msg::ExecuteMsg::Transfer {
+                        recipient: sender.clone(),
+                        amount,
+                    })?,
+                    funds: vec![],
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "claim_unbonded")
+            .add_messages(msgs))
+    }
+
+    fn execute_cw20_emergency_withdraw(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract: String,
+        amount: Uint128,
+    ) -> Result<Response<C>, ContractError> {
+        if amount.is_zero() {
+            return Err(ContractError::ZeroAmount {});
+        }
+        let config = self.config.load(deps.storage)?;
+        let early = config
+            .early_withdraw
+            .ok_or(ContractError::EarlyWithdrawDisabled {})?;
+        let sender = info.sender.into_string();
+        let penalty = self.withdraw_cw20(
+            deps.storage,
+            &env.block,
+            &sender,
+            &contract,
+            amount,
+            Some(&early),
+        )?;
+
+        //a penalty of the whole amount leaves nothing to send, cw20 rejects empty transfers
+        let mut msgs = vec![];
+        let mut claim = None;
+        if penalty < amount {
+            match config.unbonding_period {
+                //paying early does not skip the unbonding queue
+                Some(period) => {
+                    let release_at = period.after(&env.block);
+                    let claim_id = self.queue_cw20_claim(
+                        deps.storage,
+                        &sender,
+                        &contract,
+                        amount - penalty,
+                        release_at,
+                    )?;
+                    claim = Some((claim_id, release_at));
+                }
+                None => msgs.push(WasmMsg::Execute {
+                    contract_addr: contract.clone(),
+                    msg: to_binary(&cw20_base::msg::ExecuteMsg::Transfer {
+                        recipient: sender,
+                        amount: amount - penalty,
+                    })?,
+                    funds: vec![],
+                }),
+            }
+        }
+        if !penalty.is_zero() {
+            let treasury = match early.recipient {
+                PenaltyRecipient::Treasury { address } => Some(address),
+                PenaltyRecipient::Stakers {} => {
+                    if self.share_penalty(deps.storage, env.block.height, &contract, penalty)? {
+                        None
+                    } else {
+                        //with no stakers left it would sit in the pool for good
+                        Some(config.admin.into_string())
+                    }
+                }
+            };
+            if let Some(recipient) = treasury {
+                msgs.push(WasmMsg::Execute {
+                    contract_addr: contract.clone(),
+                    msg: to_binary(&cw20_base::msg::ExecuteMsg::Transfer {
+                        recipient,
+                        amount: penalty,
+                    })?,
+                    funds: vec![],
+                });
+            }
+        }
+
+        let mut res = Response::new()
+            .add_attribute("execute", "cw20_emergency_withdraw")
+            .add_attribute("contract", contract)
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("penalty", penalty.to_string());
+        if let Some((claim_id, release_at)) = claim {
+            res = res
+                .add_attribute("claim_id", claim_id.to_string())
+                .add_attribute("release_at", release_at.to_string());
+        }
+        Ok(res.add_messages(msgs))
+    }
+
+    fn execute_cw721_deposit(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        owner: String,
+        token_id: String,
+    ) -> Result<Response<C>, ContractError> {
+        let asset = AssetInfo::Cw721 {
+            contract: info.sender.into_string(),
+            token_id,
+        };
+        self.credit_asset(
+            deps.storage,
+            &env.block,
+            &owner,
+            &asset,
+            Uint128::one(),
+            Duration::Height(0),
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "cw721_deposit")
+            .add_attribute("owner", owner)
+            .add_attribute("asset", asset.key()))
+    }
+
+    fn execute_cw721_approve_operator(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        operator: String,
+        expires: Option<Expiration>,
+    ) -> Result<Response<C>, ContractError> {
+        let owner = info.sender.into_string();
+        let operator = deps.api.addr_validate(&operator)?.into_string();
+
+        let expires = expires.unwrap_or_default();
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::Expired {});
+        }
+
+        self.cw721_operators
+            .save(deps.storage, (&owner, &operator), &expires)?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "cw721_approve_operator")
+            .add_attribute("owner", owner)
+            .add_attribute("operator", operator))
+    }
+
+    fn execute_cw721_revoke_operator(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        operator: String,
+    ) -> Result<Response<C>, ContractError> {
+        let owner = info.sender.into_string();
+        let operator = deps.api.addr_validate(&operator)?.into_string();
+
+        self.cw721_operators
+            .remove(deps.storage, (&owner, &operator));
+
+        Ok(Response::new()
+            .add_attribute("execute", "cw721_revoke_operator")
+            .add_attribute("owner", owner)
+            .add_attribute("operator", operator))
+    }
+
+    fn execute_cw1155_deposit(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        owner: String,
+        batch: Vec<(String, Uint128)>,
+    ) -> Result<Response<C>, ContractError> {
+        if batch.is_empty() {
+            return Err(ContractError::EmptyBatch {});
+        }
+        let contract = info.sender.into_string();
+
+        let mut res = Response::new()
+            .add_attribute("execute", "cw1155_deposit")
+            .add_attribute("owner", owner.clone())
+            .add_attribute("contract", contract.clone());
+        for (token_id, amount) in batch {
+            if amount.is_zero() {
+                return Err(ContractError::ZeroAmount {});
+            }
+            let asset = AssetInfo::Cw1155 {
+                contract: contract.clone(),
+                token_id: token_id.clone(),
+            };
+            self.credit_asset(
+                deps.storage,
+                &env.block,
+                &owner,
+                &asset,
+                amount,
+                Duration::Height(0),
+            )?;
+            res = res.add_event(
+                Event::new("cw1155_deposit")
+                    .add_attribute("token_id", token_id)
+                    .add_attribute("amount", amount),
+            );
+        }
+        Ok(res)
+    }
+
+    fn execute_cw1155_batch_withdraw(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract: String,
+        batch: Vec<(String, Uint128)>,
+        recipient: Option<String>,
+        msg: Option<Binary>,
+    ) -> Result<Response<C>, ContractError> {
+        if batch.is_empty() {
+            return Err(ContractError::EmptyBatch {});
+        }
+        let sender = info.sender.into_string();
+        let recipient = match recipient {
+            Some(recipient) => deps.api.addr_validate(&recipient)?.into_string(),
+            None => sender.clone(),
+        };
+        for (token_id, amount) in batch.iter() {
+            let asset = AssetInfo::Cw1155 {
+                contract: contract.clone(),
+                token_id: token_id.clone(),
+            };
+            self.debit_asset(deps.storage, &env.block, &sender, &asset, *amount)?;
+        }
+
+        //with a msg the tokens are sent into the recipient contract
+        let msg = WasmMsg::Execute {
+            contract_addr: contract.clone(),
+            msg: to_binary(&Cw1155ExecuteMsg::BatchSendFrom {
+                from: env.contract.address.into_string(),
+                to: recipient.clone(),
+                batch,
+                msg,
+            })?,
+            funds: vec![],
+        };
+
+        Ok(Response::new()
+            .add_attribute("execute", "cw1155_batch_withdraw")
+            .add_attribute("owner", sender)
+            .add_attribute("recipient", recipient)
+            .add_attribute("contract", contract)
+            .add_message(msg))
+    }
+
+    fn execute_update_config(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        default_lock_duration: Option<Duration>,
+        open: Option<bool>,
+    ) -> Result<Response<C>, ContractError> {
+        let mut config = self.assert_admin(deps.storage, &info.sender)?;
+
+        if let Some(duration) = default_lock_duration {
+            config.default_lock_duration = duration;
+        }
+        if let Some(open) = open {
+            config.open = open;
+        }
+        self.config.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "update_config")
+            .add_attribute(
+                "default_lock_duration",
+                config.default_lock_duration.to_string(),
+            )
+            .add_attribute("open", config.open.to_string()))
+    }
+
+    fn execute_update_early_withdraw(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        early_withdraw: Option<EarlyWithdraw>,
+    ) -> Result<Response<C>, ContractError> {
+        let mut config = self.assert_admin(deps.storage, &info.sender)?;
+
+        config.early_withdraw = match early_withdraw {
+            Some(mut early) => {
+                if early.max_penalty > Decimal::one() {
+                    return Err(ContractError::InvalidPenalty {});
+                }
+                if let PenaltyRecipient::Treasury { address } = &mut early.recipient {
+                    *address = deps.api.addr_validate(address)?.into_string();
+                }
+                Some(early)
+            }
+            None => None,
+        };
+        self.config.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "update_early_withdraw")
+            .add_attribute("enabled", config.early_withdraw.is_some().to_string()))
+    }
+
+    fn execute_update_unbonding_period(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        unbonding_period: Option<Duration>,
+    ) -> Result<Response<C>, ContractError> {
+        let mut config = self.assert_admin(deps.storage, &info.sender)?;
+        //claims already made keep their release time
+        config.unbonding_period = unbonding_period;
+        self.config.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "update_unbonding_period")
+            .add_attribute(
+                "unbonding_period",
+                unbonding_period.map_or_else(|| "none".to_string(), |period| period.to_string()),
+            ))
+    }
+
+    fn execute_update_marketplace_fee(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        marketplace_fee: Option<MarketplaceFee>,
+    ) -> Result<Response<C>, ContractError> {
+        let mut config = self.assert_admin(deps.storage, &info.sender)?;
+
+        config.marketplace_fee = match marketplace_fee {
+            Some(mut fee) => {
+                if fee.rate > Decimal::one() {
+                    return Err(ContractError::InvalidFee {});
+                }
+                fee.collector = deps.api.addr_validate(&fee.collector)?.into_string();
+                Some(fee)
+            }
+            None => None,
+        };
+        self.config.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "update_marketplace_fee")
+            .add_attribute("enabled", config.marketplace_fee.is_some().to_string()))
+    }
+
+    fn execute_propose_new_admin(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        new_admin: String,
+    ) -> Result<Response<C>, ContractError> {
+        let mut config = self.assert_admin(deps.storage, &info.sender)?;
+
+        let new_admin = deps.api.addr_validate(&new_admin)?;
+        config.pending_admin = Some(new_admin.clone());
+        self.config.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "propose_new_admin")
+            .add_attribute("pending_admin", new_admin))
+    }
+
+    fn execute_accept_admin(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+    ) -> Result<Response<C>, ContractError> {
+        let mut config = self.config.load(deps.storage)?;
+        match config.pending_admin {
+            Some(ref pending_admin) if *pending_admin == info.sender => {}
+            Some(_) => return Err(ContractError::Unauthorized {}),
+            None => return Err(ContractError::NoPendingAdmin {}),
+        }
+
+        config.admin = info.sender;
+        config.pending_admin = None;
+        self.config.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "accept_admin")
+            .add_attribute("admin", config.admin))
+    }
+
+    fn execute_add_allowed_token(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        contract: String,
+        kind: TokenKind,
+    ) -> Result<Response<C>, ContractError> {
+        self.assert_admin(deps.storage, &info.sender)?;
+
+        let contract = deps.api.addr_validate(&contract)?.into_string();
+        self.allowed_tokens.save(deps.storage, &contract, &kind)?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "add_allowed_token")
+            .add_attribute("contract", contract))
+    }
+
+    fn execute_remove_allowed_token(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        contract: String,
+    ) -> Result<Response<C>, ContractError> {
+        self.assert_admin(deps.storage, &info.sender)?;
+
+        let contract = deps.api.addr_validate(&contract)?.into_string();
+        self.allowed_tokens.remove(deps.storage, &contract);
+
+        Ok(Response::new()
+            .add_attribute("execute", "remove_allowed_token")
+            .add_attribute("contract", contract))
+    }
+
+    fn execute_set_reward_pool(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        staked_token: String,
+        reward: RewardToken,
+        reward_per_block: Uint128,
+    ) -> Result<Response<C>, ContractError> {
+        self.assert_admin(deps.storage, &info.sender)?;
+
+        let (staked_token, reward) = self.save_reward_pool(
+            deps,
+            env.block.height,
+            PoolKind::Cw20,
+            staked_token,
+            reward,
+            reward_per_block,
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "set_reward_pool")
+            .add_attribute("staked_token", staked_token)
+            .add_attribute("reward", reward.key())
+            .add_attribute("reward_per_block", reward_per_block))
+    }
+
+    fn execute_fund_rewards(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        staked_token: String,
+    ) -> Result<Response<C>, ContractError> {
+        if info.funds.is_empty() || info.funds.iter().any(|c| c.amount.is_zero()) {
+            return Err(ContractError::InvalidCoin {});
+        }
+        for fund in info.funds.iter() {
+            let reward = RewardToken::Native {
+                denom: fund.denom.clone(),
+            };
+            self.fund_reward_pool(
+                deps.storage,
+                env.block.height,
+                PoolKind::Cw20,
+                &staked_token,
+                &reward,
+                fund.amount,
+            )?;
+        }
+
+        Ok(Response::new()
+            .add_attribute("execute", "fund_rewards")
+            .add_attribute("staked_token", staked_token)
+            .add_attribute("funder", info.sender))
+    }
+
+    fn execute_cw20_fund_rewards(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        staked_token: String,
+        amount: Uint128,
+    ) -> Result<Response<C>, ContractError> {
+        let reward = RewardToken::Cw20 {
+            contract: info.sender.into_string(),
+        };
+        self.fund_reward_pool(
+            deps.storage,
+            env.block.height,
+            PoolKind::Cw20,
+            &staked_token,
+            &reward,
+            amount,
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "cw20_fund_rewards")
+            .add_attribute("staked_token", staked_token)
+            .add_attribute("reward", reward.key())
+            .add_attribute("amount", amount))
+    }
+
+    fn execute_set_nft_reward_pool(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        collection: String,
+        reward: RewardToken,
+        reward_per_block: Uint128,
+    ) -> Result<Response<C>, ContractError> {
+        self.assert_admin(deps.storage, &info.sender)?;
+
+        let (collection, reward) = self.save_reward_pool(
+            deps,
+            env.block.height,
+            PoolKind::Cw721,
+            collection,
+            reward,
+            reward_per_block,
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "set_nft_reward_pool")
+            .add_attribute("collection", collection)
+            .add_attribute("reward", reward.key())
+            .add_attribute("reward_per_block", reward_per_block))
+    }
+
+    fn execute_fund_nft_rewards(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        collection: String,
+    ) -> Result<Response<C>, ContractError> {
+        if info.funds.is_empty() || info.funds.iter().any(|c| c.amount.is_zero()) {
+            return Err(ContractError::InvalidCoin {});
+        }
+        for fund in info.funds.iter() {
+            let reward = RewardToken::Native {
+                denom: fund.denom.clone(),
+            };
+            self.fund_reward_pool(
+                deps.storage,
+                env.block.height,
+                PoolKind::Cw721,
+                &collection,
+                &reward,
+                fund.amount,
+            )?;
+        }
+
+        Ok(Response::new()
+            .add_attribute("execute", "fund_nft_rewards")
+            .add_attribute("collection", collection)
+            .add_attribute("funder", info.sender))
+    }
+
+    fn execute_cw20_fund_nft_rewards(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        collection: String,
+        amount: Uint128,
+    ) -> Result<Response<C>, ContractError> {
+        let reward = RewardToken::Cw20 {
+            contract: info.sender.into_string(),
+        };
+        self.fund_reward_pool(
+            deps.storage,
+            env.block.height,
+            PoolKind::Cw721,
+            &collection,
+            &reward,
+            amount,
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "cw20_fund_nft_rewards")
+            .add_attribute("collection", collection)
+            .add_attribute("reward", reward.key())
+            .add_attribute("amount", amount))
+    }
+
+    fn execute_claim_nft_rewards(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response<C>, ContractError> {
+        let sender = info.sender.into_string();
+        let tokens: Vec<(String, String)> = self
+            .cw721_deposits
+            .idx
+            .owner
+            .prefix(sender.clone())
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()?;
+
+        //tokens come grouped by collection, so each collection's pools are accrued once
+        let mut payouts = vec![];
+        let mut pools: Vec<(String, RewardPool)> = vec![];
+        let mut collection = String::new();
+        for (token_collection, token_id) in tokens {
+            if token_collection != collection {
+                collection = token_collection;
+                pools = self.accrued_pools(
+                    deps.storage,
+                    PoolKind::Cw721,
+                    &collection,
+                    env.block.height,
+                )?;
+                self.save_pools(deps.storage, PoolKind::Cw721, &collection, &pools)?;
+            }
+            for (key, reward, mut stake) in
+                self.settled_nft_stakes(deps.storage, &collection, &token_id, &pools)?
+            {
+                add_payout(&mut payouts, reward, stake.pending);
+                stake.pending = Uint128::zero();
+                self.nft_reward_stakes.save(
+                    deps.storage,
+                    (&collection, &token_id, &key),
+                    &stake,
+                )?;
+            }
+        }
+        if payouts.is_empty() {
+            return Err(ContractError::NoRewardsToClaim {});
+        }
+
+        let mut res = Response::new().add_attribute("execute", "claim_nft_rewards");
+        for (reward, amount) in payouts {
+            res = res
+                .add_attribute(format!("claimed_{}", reward.key()), amount)
+                .add_message(reward_msg(reward, &sender, amount)?);
+        }
+        Ok(res)
+    }
+
+    fn execute_claim_rewards(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        staked_token: String,
+    ) -> Result<Response<C>, ContractError> {
+        let sender = info.sender.into_string();
+        self.settle_rewards(deps.storage, env.block.height, &staked_token, &sender)?;
+
+        let mut res = Response::new()
+            .add_attribute("execute", "claim_rewards")
+            .add_attribute("staked_token", staked_token.clone());
+        let pools: Vec<(String, RewardPool)> = self
+            .reward_pools
+            .prefix(&staked_token)
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()?;
+        for (key, pool) in pools {
+            let mut stake = self
+                .reward_stakes
+                .load(deps.storage, (&staked_token, &key, &sender))?;
+            if stake.pending.is_zero() {
+                continue;
+            }
+            let msg = reward_msg(pool.reward, &sender, stake.pending)?;
+            res = res
+                .add_attribute(format!("claimed_{}", key), stake.pending)
+                .add_message(msg);
+            stake.pending = Uint128::zero();
+            self.reward_stakes
+                .save(deps.storage, (&staked_token, &key, &sender), &stake)?;
+        }
+        if res.messages.is_empty() {
+            return Err(ContractError::NoRewardsToClaim {});
+        }
+        Ok(res)
+    }
+
+    fn execute_place_bid(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        contract: String,
+        token_id: String,
+    ) -> Result<Response<C>, ContractError> {
+        let amount = match info.funds.as_slice() {
+            [coin] if !coin.amount.is_zero() => coin.clone(),
+            _ => return Err(ContractError::InvalidCoin {}),
+        };
+        let deposit = self
+            .cw721_deposits
+            .may_load(deps.storage, (&contract, &token_id))?
+            .ok_or_else(|| ContractError::NftNotDeposited {
+                contract: contract.clone(),
+                token_id: token_id.clone(),
+            })?;
+        let bidder = info.sender.into_string();
+        if deposit.owner == bidder {
+            return Err(ContractError::CannotBidOnOwnToken {});
+        }
+
+        let previous = self
+            .offers
+            .may_load(deps.storage, (&contract, &token_id, &bidder))?;
+        let offer = Offer {
+            token_id: token_id.clone(),
+            amount: amount.clone(),
+        };
+        self.offers
+            .save(deps.storage, (&contract, &token_id, &bidder), &offer)?;
+
+        let mut res = Response::new()
+            .add_attribute("execute", "place_bid")
+            .add_attribute("contract", contract)
+            .add_attribute("token_id", token_id)
+            .add_attribute("bidder", bidder.clone())
+            .add_attribute("amount", amount.to_string());
+        //an earlier bid of the same bidder is replaced and refunded
+        if let Some(previous) = previous {
+            res = res.add_message(BankMsg::Send {
+                to_address: bidder,
+                amount: vec![previous.amount],
+            });
+        }
+        Ok(res)
+    }
+
+    fn execute_cancel_bid(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        contract: String,
+        token_id: String,
+    ) -> Result<Response<C>, ContractError> {
+        let bidder = info.sender.into_string();
+        let offer = self
+            .offers
+            .may_load(deps.storage, (&contract, &token_id, &bidder))?
+            .ok_or_else(|| ContractError::NoBid {
+                bidder: bidder.clone(),
+            })?;
+        self.offers
+            .remove(deps.storage, (&contract, &token_id, &bidder));
+
+        Ok(Response::new()
+            .add_attribute("execute", "cancel_bid")
+            .add_attribute("contract", contract)
+            .add_attribute("token_id", token_id)
+            .add_attribute("bidder", bidder.clone())
+            .add_message(BankMsg::Send {
+                to_address: bidder,
+                amount: vec![offer.amount],
+            }))
+    }
+
+    fn execute_accept_bid(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract: String,
+        token_id: String,
+        bidder: String,
+    ) -> Result<Response<C>, ContractError> {
+        let offer = self
+            .offers
+            .may_load(deps.storage, (&contract, &token_id, &bidder))?
+            .ok_or_else(|| ContractError::NoBid {
+                bidder: bidder.clone(),
+            })?;
+        self.offers
+            .remove(deps.storage, (&contract, &token_id, &bidder));
+
+        //the nft stays in escrow, only the ledger changes hands
+        let seller = info.sender.into_string();
+        let payment = AssetInfo::Native {
+            denom: offer.amount.denom.clone(),
+        };
+        let sale_msgs = self.sale_msgs(
+            deps.storage,
+            &env.contract.address,
+            &payment,
+            offer.amount.amount,
+            &seller,
+        )?;
+        let asset = AssetInfo::Cw721 {
+            contract: contract.clone(),
+            token_id: token_id.clone(),
+        };
+        let reward_msgs =
+            self.debit_asset(deps.storage, &env.block, &seller, &asset, Uint128::one())?;
+        self.credit_asset(
+            deps.storage,
+            &env.block,
+            &bidder,
+            &asset,
+            Uint128::one(),
+            Duration::Height(0),
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "accept_bid")
+            .add_attribute("seller", seller)
+            .add_attribute("bidder", bidder)
+            .add_attribute("asset", asset.key())
+            .add_attribute("amount", offer.amount.to_string())
+            .add_messages(sale_msgs)
+            .add_messages(reward_msgs))
+    }
+
+    fn execute_list_nft(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        contract: String,
+        token_id: String,
+        payment: AssetInfo,
+        price: Uint128,
+    ) -> Result<Response<C>, ContractError> {
+        if price.is_zero() {
+            return Err(ContractError::ZeroAmount {});
+        }
+        self.assert_payment_asset(deps.as_ref(), &payment)?;
+        let seller = info.sender.into_string();
+        self.assert_nft_for_sale(deps.storage, &contract, &token_id, &seller)?;
+
+        let listing = Listing {
+            seller,
+            contract: contract.clone(),
+            token_id: token_id.clone(),
+            payment,
+            price,
+        };
+        self.listings
+            .save(deps.storage, (&contract, &token_id), &listing)?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "list_nft")
+            .add_attribute("seller", listing.seller)
+            .add_attribute("contract", contract)
+            .add_attribute("token_id", token_id)
+            .add_attribute("payment", listing.payment.key())
+            .add_attribute("price", price.to_string()))
+    }
+
+    fn execute_delist_nft(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        contract: String,
+        token_id: String,
+    ) -> Result<Response<C>, ContractError> {
+        let listing = self.load_listing(deps.storage, &contract, &token_id)?;
+        if listing.seller != info.sender {
+            return Err(ContractError::InvalidOwner {});
+        }
+        self.listings.remove(deps.storage, (&contract, &token_id));
+
+        Ok(Response::new()
+            .add_attribute("execute", "delist_nft")
+            .add_attribute("contract", contract)
+            .add_attribute("token_id", token_id))
+    }
+
+    fn execute_buy_nft(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        buyer: String,
+        contract: String,
+        token_id: String,
+        payment: AssetInfo,
+        amount: Uint128,
+    ) -> Result<Response<C>, ContractError> {
+        let listing = self.load_listing(deps.storage, &contract, &token_id)?;
+        if listing.payment != payment || listing.price != amount {
+            return Err(ContractError::IncorrectPayment {
+                expected: format!("{}{}", listing.price, listing.payment.key()),
+            });
+        }
+
+        //the nft stays in escrow, debiting the seller also drops the listing
+        let asset = AssetInfo::Cw721 {
+            contract: contract.clone(),
+            token_id: token_id.clone(),
+        };
+        let reward_msgs = self.debit_asset(
+            deps.storage,
+            &env.block,
+            &listing.seller,
+            &asset,
+            Uint128::one(),
+        )?;
+        self.credit_asset(
+            deps.storage,
+            &env.block,
+            &buyer,
+            &asset,
+            Uint128::one(),
+            Duration::Height(0),
+        )?;
+        let sale_msgs = self.sale_msgs(
+            deps.storage,
+            &env.contract.address,
+            &payment,
+            amount,
+            &listing.seller,
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "buy_nft")
+            .add_attribute("seller", listing.seller)
+            .add_attribute("buyer", buyer)
+            .add_attribute("asset", asset.key())
+            .add_attribute("price", format!("{}{}", amount, payment.key()))
+            .add_messages(sale_msgs)
+            .add_messages(reward_msgs))
+    }
+
+    fn execute_start_auction(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract: String,
+        token_id: String,
+        payment: AssetInfo,
+        kind: AuctionKind,
+        start_price: Uint128,
+        duration: Duration,
+    ) -> Result<Response<C>, ContractError> {
+        if start_price.is_zero() {
+            return Err(ContractError::ZeroAmount {});
+        }
+        self.assert_payment_asset(deps.as_ref(), &payment)?;
+        let start = match duration {
+            Duration::Height(0) | Duration::Time(0) => {
+                return Err(ContractError::InvalidAuctionDuration {})
+            }
+            Duration::Height(_) => Expiration::AtHeight(env.block.height),
+            Duration::Time(_) => Expiration::AtTime(env.block.time),
+        };
+        match &kind {
+            AuctionKind::English {
+                min_increment,
+                extension,
+            } => {
+                if min_increment.is_zero() {
+                    return Err(ContractError::ZeroAmount {});
+                }
+                match (duration, extension) {
+                    (Duration::Height(_), Duration::Height(_))
+                    | (Duration::Time(_), Duration::Time(_)) => {}
+                    _ => return Err(ContractError::InvalidAuctionDuration {}),
+                }
+            }
+            AuctionKind::Dutch { end_price } => {
+                if *end_price >= start_price {
+                    return Err(ContractError::InvalidDutchPrices {});
+                }
+            }
+        }
+        let seller = info.sender.into_string();
+        self.assert_nft_for_sale(deps.storage, &contract, &token_id, &seller)?;
+        //the auction takes over from a fixed price listing
+        self.listings.remove(deps.storage, (&contract, &token_id));
+
+        let id = self.auction_seq.may_load(deps.storage)?.unwrap_or_default() + 1;
+        self.auction_seq.save(deps.storage, &id)?;
+        let auction = Auction {
+            id,
+            seller,
+            contract: contract.clone(),
+            token_id: token_id.clone(),
+            payment,
+            kind,
+            start_price,
+            start,
+            end: duration.after(&env.block),
+            highest_bid: None,
+            bid_count: 0,
+        };
+        self.auctions
+            .save(deps.storage, (&contract, &token_id), &auction)?;
+
+        Ok(Response::new()
+            .add_attribute("execute", "start_auction")
+            .add_attribute("auction_id", id.to_string())
+            .add_attribute("seller", auction.seller)
+            .add_attribute("contract", contract)
+            .add_attribute("token_id", token_id)
+            .add_attribute("end", auction.end.to_string()))
+    }
+
+    fn execute_cancel_auction(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        contract: String,
+        token_id: String,
+    ) -> Result<Response<C>, ContractError> {
+        let auction = self.load_auction(deps.storage, &contract, &token_id)?;
+        if auction.seller != info.sender {
+            return Err(ContractError::InvalidOwner {});
+        }
+        if auction.highest_bid.is_some() {
+            return Err(ContractError::AuctionHasBids {});
+        }
+        self.auctions.remove(deps.storage, (&contract, &token_id));
+
+        Ok(Response::new()
+            .add_attribute("execute", "cancel_auction")
+            .add_attribute("auction_id", auction.id.to_string()))
+    }
+
+    fn execute_bid_auction(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        bidder: String,
+        contract: String,
+        token_id: String,
+        payment: AssetInfo,
+        amount: Uint128,
+    ) -> Result<Response<C>, ContractError> {
+        let mut auction = self.load_auction(deps.storage, &contract, &token_id)?;
+        if auction.end.is_expired(&env.block) {
+            return Err(ContractError::AuctionEnded {});
+        }
+        if auction.seller == bidder {
+            return Err(ContractError::CannotBidOnOwnToken {});
+        }
+        if auction.payment != payment {
+            return Err(ContractError::IncorrectPayment {
+                expected: auction.payment.key(),
+            });
+        }
+        let min_bid = auction_min_bid(&auction, &env.block);
+        if amount < min_bid {
+            return Err(ContractError::BidTooLow { min: min_bid });
+        }
+
+        let mut msgs: Vec<CosmosMsg<C>> = vec![];
+        let bid = match &auction.kind {
+            AuctionKind::English { extension, .. } => {
+                if let Some(outbid) = &auction.highest_bid {
+                    msgs.push(asset_msg(
+                        &env.contract.address,
+                        &payment,
+                        outbid.amount,
+                        &outbid.bidder,
+                        None,
+                    )?);
+                }
+                //a late bid keeps the auction open long enough to be answered
+                let extended = extension.after(&env.block);
+                if extended > auction.end {
+                    auction.end = extended;
+                }
+                AuctionBid {
+                    bidder,
+                    amount,
+                    height: env.block.height,
+                }
+            }
+            AuctionKind::Dutch { .. } => {
+                //the winner pays the current price, anything above it goes back
+                if amount > min_bid {
+                    msgs.push(asset_msg(
+                        &env.contract.address,
+                        &payment,
+                        amount - min_bid,
+                        &bidder,
+                        None,
+                    )?);
+                }
+                AuctionBid {
+                    bidder,
+                    amount: min_bid,
+                    height: env.block.height,
+                }
+            }
+        };
+        auction.bid_count += 1;
+        self.auction_bids
+            .save(deps.storage, (auction.id, auction.bid_count), &bid)?;
+        auction.highest_bid = Some(bid.clone());
+
+        let res = Response::new()
+            .add_attribute("execute", "bid_auction")
+            .add_attribute("auction_id", auction.id.to_string())
+            .add_attribute("bidder", bid.bidder)
+            .add_attribute("amount", format!("{}{}", bid.amount, payment.key()));
+        let res = match auction.kind {
+            //the first bid takes a dutch auction
+            AuctionKind::Dutch { .. } => {
+                msgs.extend(self.close_auction(deps.storage, &env, &auction)?);
+                res.add_attribute("settled", "true")
+            }
+            AuctionKind::English { .. } => {
+                self.auctions
+                    .save(deps.storage, (&contract, &token_id), &auction)?;
+                res.add_attribute("end", auction.end.to_string())
+            }
+        };
+        Ok(res.add_messages(msgs))
+    }
+
+    fn execute_settle_auction(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        contract: String,
+        token_id: String,
+    ) -> Result<Response<C>, ContractError> {
+        let auction = self.load_auction(deps.storage, &contract, &token_id)?;
+        if !auction.end.is_expired(&env.block) {
+            return Err(ContractError::AuctionNotEnded {});
+        }
+        let msgs = self.close_auction(deps.storage, &env, &auction)?;
+        let winner = match auction.highest_bid {
+            Some(bid) => bid.bidder,
+            None => auction.seller,
+        };
+
+        Ok(Response::new()
+            .add_attribute("execute", "settle_auction")
+            .add_attribute("auction_id", auction.id.to_string())
+            .add_attribute("winner", winner)
+            .add_messages(msgs))
+    }
+
+    fn execute_update_cw20_lock_duration(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        contract: String,
+        duration: Option<Duration>,
+    ) -> Result<Response<C>, ContractError> {
+        self.assert_admin(deps.storage, &info.sender)?;
+
+        let contract = deps.api.addr_validate(&contract)?.into_string();
+        match duration {
+            Some(duration) => self
+                .cw20_lock_durations
+                .save(deps.storage, &contract, &duration)?,
+            None => self.cw20_lock_durations.remove(deps.storage, &contract),
+        }
+
+        Ok(Response::new()
+            .add_attribute("execute", "update_cw20_lock_duration")
+            .add_attribute("contract", contract)
+            .add_attribute(
+                "duration",
+                duration.map_or_else(|| "default".to_string(), |d| d.to_string()),
+            ))
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    let contract = Deposit::<Empty>::default();
+    match msg {
+        ExecuteMsg::Deposit {} => contract.execute_deposit(deps, env, info),
+        ExecuteMsg::Withdraw {
+            asset,
+            amount,
+            recipient,
+            msg,
+        } => contract.execute_withdraw(deps, env, info, asset, amount, recipient, msg),
+        ExecuteMsg::Receive(ReceiveMsg::Cw20(cw20_msg)) => {
+            receive_cw20(deps, env, info, &contract, cw20_msg)
+        }
+        ExecuteMsg::ReceiveNft(cw721_msg) => receive_cw721(deps, env, info, &contract, cw721_msg),
+        //a single receive is handled as a batch of one
+        ExecuteMsg::Receive(ReceiveMsg::Cw1155(cw1155_msg)) => {
+            let batch_msg = Cw1155BatchReceiveMsg {
+                operator: cw1155_msg.operator,
+                from: cw1155_msg.from,
+                batch: vec![(cw1155_msg.token_id, cw1155_msg.amount)],
+                msg: cw1155_msg.msg,
+            };
+            receive_cw1155(deps, env, info, &contract, batch_msg)
+        }
+        ExecuteMsg::BatchReceive(cw1155_msg) => {
+            receive_cw1155(deps, env, info, &contract, cw1155_msg)
+        }
+        ExecuteMsg::Withdraw1155 {
+            contract_addr,
+            token_id,
+            amount,
+            recipient,
+            msg,
+        } => {
+            let asset = AssetInfo::Cw1155 {
+                contract: contract_addr,
+                token_id,
+            };
+            contract.execute_withdraw(deps, env, info, asset, amount, recipient, msg)
+        }
+        ExecuteMsg::BatchWithdraw1155 {
+            contract_addr,
+            batch,
+            recipient,
+            msg,
+        } => contract.execute_cw1155_batch_withdraw(
+            deps,
+            env,
+            info,
+            contract_addr,
+            batch,
+            recipient,
+            msg,
+        ),
+        ExecuteMsg::EmergencyWithdrawCw20 { address, amount } => {
+            contract.execute_cw20_emergency_withdraw(deps, env, info, address, amount)
+        }
+        ExecuteMsg::TransferDeposit {
+            recipient,
+            asset,
+            amount,
+        } => contract.execute_transfer_deposit(deps, env, info, recipient, asset, amount),
+        ExecuteMsg::IncreaseAllowance {
+            spender,
+            asset,
+            amount,
+            expires,
+        } => contract.execute_increase_allowance(deps, env, info, spender, asset, amount, expires),
+        ExecuteMsg::DecreaseAllowance {
+            spender,
+            asset,
+            amount,
+            expires,
+        } => contract.execute_decrease_allowance(deps, env, info, spender, asset, amount, expires),
+        ExecuteMsg::WithdrawFrom {
+            owner,
+            asset,
+            amount,
+            recipient,
+        } => contract.execute_withdraw_from(deps, env, info, owner, asset, amount, recipient),
+        ExecuteMsg::UnbondCw20 { address, amount } => {
+            contract.execute_cw20_unbond(deps, env, info, address, amount)
+        }
+        ExecuteMsg::ClaimUnbonded {} => contract.execute_claim_unbonded(deps, env, info),
+        ExecuteMsg::ApproveNftOperator { operator, expires } => {
+            contract.execute_cw721_approve_operator(deps, env, info, operator, expires)
+        }
+        ExecuteMsg::RevokeNftOperator { operator } => {
+            contract.execute_cw721_revoke_operator(deps, info, operator)
+        }
+        ExecuteMsg::UpdateConfig {
+            default_lock_duration,
+            open,
+        } => contract.execute_update_config(deps, info, default_lock_duration, open),
+        ExecuteMsg::UpdateEarlyWithdraw { early_withdraw } => {
+            contract.execute_update_early_withdraw(deps, info, early_withdraw)
+        }
+        ExecuteMsg::UpdateMarketplaceFee { marketplace_fee } => {
+            contract.execute_update_marketplace_fee(deps, info, marketplace_fee)
+        }
+        ExecuteMsg::UpdateUnbondingPeriod { unbonding_period } => {
+            contract.execute_update_unbonding_period(deps, info, unbonding_period)
+        }
+        ExecuteMsg::ProposeNewAdmin { new_admin } => {
+            contract.execute_propose_new_admin(deps, info, new_admin)
+        }
+        ExecuteMsg::AcceptAdmin {} => contract.execute_accept_admin(deps, info),
+        ExecuteMsg::AddAllowedToken {
+            contract: token_contract,
+            kind,
+        } => contract.execute_add_allowed_token(deps, info, token_contract, kind),
+        ExecuteMsg::RemoveAllowedToken {
+            contract: token_contract,
+        } => contract.execute_remove_allowed_token(deps, info, token_contract),
+        ExecuteMsg::UpdateCw20LockDuration {
+            contract: cw20_contract,
+            duration,
+        } => contract.execute_update_cw20_lock_duration(deps, info, cw20_contract, duration),
+        ExecuteMsg::SetRewardPool {
+            staked_token,
+            reward,
+            reward_per_block,
+        } => contract.execute_set_reward_pool(
+            deps,
+            env,
+            info,
+            staked_token,
+            reward,
+            reward_per_block,
+        ),
+        ExecuteMsg::FundRewards { staked_token } => {
+            contract.execute_fund_rewards(deps, env, info, staked_token)
+        }
+        ExecuteMsg::ClaimRewards { staked_token } => {
+            contract.execute_claim_rewards(deps, env, info, staked_token)
+        }
+        ExecuteMsg::PlaceBid {
+            contract_addr,
+            token_id,
+        } => contract.execute_place_bid(deps, info, contract_addr, token_id),
+        ExecuteMsg::CancelBid {
+            contract_addr,
+            token_id,
+        } => contract.execute_cancel_bid(deps, info, contract_addr, token_id),
+        ExecuteMsg::ListNft {
+            contract_addr,
+            token_id,
+            payment,
+            price,
+        } => contract.execute_list_nft(deps, info, contract_addr, token_id, payment, price),
+        ExecuteMsg::DelistNft {
+            contract_addr,
+            token_id,
+        } => contract.execute_delist_nft(deps, info, contract_addr, token_id),
+        ExecuteMsg::BuyNft {
+            contract_addr,
+            token_id,
+        } => {
+            let paid = match info.funds.as_slice() {
+                [coin] => coin.clone(),
+                _ => return Err(ContractError::InvalidCoin {}),
+            };
+            let payment = AssetInfo::Native { denom: paid.denom };
+            let buyer = info.sender.into_string();
+            contract.execute_buy_nft(
+                deps,
+                env,
+                buyer,
+                contract_addr,
+                token_id,
+                payment,
+                paid.amount,
+            )
+        }
+        ExecuteMsg::StartAuction {
+            contract_addr,
+            token_id,
+            payment,
+            kind,
+            start_price,
+            duration,
+        } => contract.execute_start_auction(
+            deps,
+            env,
+            info,
+            contract_addr,
+            token_id,
+            payment,
+            kind,
+            start_price,
+            duration,
+        ),
+        ExecuteMsg::CancelAuction {
+            contract_addr,
+            token_id,
+        } => contract.execute_cancel_auction(deps, info, contract_addr, token_id),
+        ExecuteMsg::BidAuction {
+            contract_addr,
+            token_id,
+        } => {
+            let paid = match info.funds.as_slice() {
+                [coin] => coin.clone(),
+                _ => return Err(ContractError::InvalidCoin {}),
+            };
+            let payment = AssetInfo::Native { denom: paid.denom };
+            let bidder = info.sender.into_string();
+            contract.execute_bid_auction(
+                deps,
+                env,
+                bidder,
+                contract_addr,
+                token_id,
+                payment,
+                paid.amount,
+            )
+        }
+        ExecuteMsg::SettleAuction {
+            contract_addr,
+            token_id,
+        } => contract.execute_settle_auction(deps, env, contract_addr, token_id),
+        ExecuteMsg::AcceptBid {
+            contract_addr,
+            token_id,
+            bidder,
+        } => contract.execute_accept_bid(deps, env, info, contract_addr, token_id, bidder),
+        ExecuteMsg::SetNftRewardPool {
+            collection,
+            reward,
+            reward_per_block,
+        } => contract.execute_set_nft_reward_pool(
+            deps,
+            env,
+            info,
+            collection,
+            reward,
+            reward_per_block,
+        ),
+        ExecuteMsg::FundNftRewards { collection } => {
+            contract.execute_fund_nft_rewards(deps, env, info, collection)
+        }
+        ExecuteMsg::ClaimNftRewards {} => contract.execute_claim_nft_rewards(deps, env, info),
+    }
+}
+
+impl<'a, C> DepositQuery for Deposit<'a, C>
+where
+    C: CustomMsg,
+{
+    fn query_deposits(
+        &self,
+        deps: Deps,
+        address: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<DepositResponse> {
+        let limit = page_limit(limit);
+        let start = start_after.as_deref().map(Bound::exclusive);
+        let deposits: Vec<(String, Deposits)> = self
+            .deposits
+            .prefix(&address)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        let next_start_after = next_cursor(&deposits, limit);
+        Ok(DepositResponse {
+            deposits,
+            next_start_after,
+        })
+    }
+
+    fn query_deposits_at(
+        &self,
+        deps: Deps,
+        address: String,
+        height: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<DepositResponse> {
+        let limit = page_limit(limit);
+        let start = start_after.as_deref().map(Bound::exclusive);
+        //positions are never removed, so the current keys cover every past one
+        let keys: Vec<String> = self
+            .deposits
+            .prefix(&address)
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        let next_start_after = page_cursor(&keys, limit);
+        let mut deposits: Vec<(String, Deposits)> = vec![];
+        for denom in keys {
+            if let Some(deposit) =
+                self.deposits
+                    .may_load_at_height(deps.storage, (&address, &denom), height)?
+            {
+                deposits.push((denom, deposit));
+            }
+        }
+        Ok(DepositResponse {
+            deposits,
+            next_start_after,
+        })
+    }
+
+    fn query_cw20_deposits_at(
+        &self,
+        deps: Deps,
+        address: String,
+        height: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Cw20DepositResponse> {
+        let limit = page_limit(limit);
+        let start = start_after.as_deref().map(Bound::exclusive);
+        //positions are never removed, so the current keys cover every past one
+        let keys: Vec<String> = self
+            .cw20_deposits
+            .prefix(&address)
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        let next_start_after = page_cursor(&keys, limit);
+        let mut deposits: Vec<(String, Cw20Deposits)> = vec![];
+        for contract in keys {
+            if let Some(deposit) = self.cw20_deposits.may_load_at_height(
+                deps.storage,
+                (&address, &contract),
+                height,
+            )? {
+                deposits.push((contract, deposit));
+            }
+        }
+        Ok(Cw20DepositResponse {
+            deposits,
+            next_start_after,
+        })
+    }
+
+    fn query_cw20_deposits(
+        &self,
+        deps: Deps,
+        address: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Cw20DepositResponse> {
+        let limit = page_limit(limit);
+        let start = start_after.as_deref().map(Bound::exclusive);
+        let deposits: Vec<(String, Cw20Deposits)> = self
+            .cw20_deposits
+            .prefix(&address)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        let next_start_after = next_cursor(&deposits, limit);
+        Ok(Cw20DepositResponse {
+            deposits,
+            next_start_after,
+        })
+    }
+
+    fn query_cw721_by_contract(
+        &self,
+        deps: Deps,
+        contract: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Cw721DepositResponse> {
+        let limit = page_limit(limit);
+        let start = start_after.as_deref().map(Bound::exclusive);
+        let deposits: Vec<(String, Cw721Deposits)> = self
+            .cw721_deposits
+            .prefix(&contract)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        let next_start_after = next_cursor(&deposits, limit);
+        Ok(Cw721DepositResponse {
+            deposits,
+            next_start_after,
+        })
+    }
+
+    fn query_cw721_by_owner(
+        &self,
+        deps: Deps,
+        address: String,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    ) -> StdResult<Cw721DepositsByOwnerResponse> {
+        let limit = page_limit(limit);
+        let start = start_after
+            .as_ref()
+            .map(|(contract, token_id)| Bound::exclusive((contract.as_str(), token_id.as_str())));
+        let deposits: Vec<((String, String), Cw721Deposits)> = self
+            .cw721_deposits
+            .idx
+            .owner
+            .prefix(address)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        let next_start_after = next_cursor(&deposits, limit);
+        Ok(Cw721DepositsByOwnerResponse {
+            deposits,
+            next_start_after,
+        })
+    }
+
+    fn query_cw1155_by_contract(
+        &self,
+        deps: Deps,
+        contract: String,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    ) -> StdResult<Cw1155DepositResponse> {
+        let limit = page_limit(limit);
+        let start = start_after
+            .as_ref()
+            .map(|(token_id, owner)| Bound::exclusive((token_id.as_str(), owner.as_str())));
+        let deposits: Vec<((String, String), Cw1155Deposits)> = self
+            .cw1155_deposits
+            .sub_prefix(&contract)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        let next_start_after = next_cursor(&deposits, limit);
+        Ok(Cw1155DepositResponse {
+            deposits,
+            next_start_after,
+        })
+    }
+
+    fn query_cw1155_by_owner(
+        &self,
+        deps: Deps,
+        address: String,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    ) -> StdResult<Cw1155DepositsByOwnerResponse> {
+        let limit = page_limit(limit);
+        let start = start_after.as_ref().map(|(contract, token_id)| {
+            Bound::exclusive((contract.as_str(), token_id.as_str(), address.as_str()))
+        });
+        let deposits: Vec<((String, String), Cw1155Deposits)> = self
+            .cw1155_deposits
+            .idx
+            .owner
+            .prefix(address.clone())
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                item.map(|((contract, token_id, _), deposit)| ((contract, token_id), deposit))
+            })
+            .collect::<StdResult<_>>()?;
+        let next_start_after = next_cursor(&deposits, limit);
+        Ok(Cw1155DepositsByOwnerResponse {
+            deposits,
+            next_start_after,
+        })
+    }
+
+    fn query_cw721_deposit_changelog(
+        &self,
+        deps: Deps,
+        contract: String,
+        token_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<Cw721DepositChangelogResponse> {
+        let limit = page_limit(limit);
+        let start = start_after.map(Bound::exclusive);
+        let changelog: Vec<(u64, Option<Cw721Deposits>)> = self
+            .cw721_deposits
+            .changelog()
+            .prefix((&contract, &token_id))
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(height, change)| (height, change.old)))
+            .collect::<StdResult<_>>()?;
+        let next_start_after = next_cursor(&changelog, limit);
+        Ok(Cw721DepositChangelogResponse {
+            changelog,
+            next_start_after,
+        })
+    }
+
+    fn query_cw721_deposit_at(
+        &self,
+        deps: Deps,
+        contract: String,
+        token_id: String,
+        height: u64,
+    ) -> StdResult<Cw721DepositAtResponse> {
+        let deposit =
+            self.cw721_deposits
+                .may_load_at_height(deps.storage, (&contract, &token_id), height)?;
+        Ok(Cw721DepositAtResponse { deposit, height })
+    }
+
+    fn query_cw721_by_owner_at(
+        &self,
+        deps: Deps,
+        address: String,
+        height: u64,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    ) -> StdResult<Cw721DepositsByOwnerResponse> {
+        let limit = page_limit(limit);
+        let start = start_after
+            .as_ref()
+            .map(|(contract, token_id)| Bound::exclusive((contract.as_str(), token_id.as_str())));
+        //a page looks at limit of the tokens address ever held and keeps the
+        //ones it held at height, so the cursor follows the tokens looked at
+        let held: Vec<(String, String)> = self
+            .cw721_custody
+            .sub_prefix(&address)
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        let next_start_after = page_cursor(&held, limit);
+        let mut deposits: Vec<((String, String), Cw721Deposits)> = vec![];
+        for (contract, token_id) in held {
+            if let Some(deposit) = self.cw721_deposits.may_load_at_height(
+                deps.storage,
+                (&contract, &token_id),
+                height,
+            )? {
+                if deposit.owner.as_str() == address {
+                    deposits.push(((contract, token_id), deposit));
+                }
+            }
+        }
+        Ok(Cw721DepositsByOwnerResponse {
+            deposits,
+            next_start_after,
+        })
+    }
+
+    fn query_cw721_operators(
+        &self,
+        deps: Deps,
+        address: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Cw721OperatorsResponse> {
+        let limit = page_limit(limit);
+        let start = start_after.as_deref().map(Bound::exclusive);
+        let operators: Vec<(String, Expiration)> = self
+            .cw721_operators
+            .prefix(&address)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        let next_start_after = next_cursor(&operators, limit);
+        Ok(Cw721OperatorsResponse {
+            operators,
+            next_start_after,
+        })
+    }
+
+    fn query_totals(
+        &self,
+        deps: Deps,
+        kind: AssetKind,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TotalsResponse> {
+        let limit = page_limit(limit);
+        let start = start_after.as_deref().map(Bound::exclusive);
+        let map = match kind {
+            AssetKind::Native => &self.native_totals,
+            AssetKind::Cw20 => &self.cw20_totals,
+            AssetKind::Cw721 => &self.cw721_totals,
+            AssetKind::Cw1155 => &self.cw1155_totals,
+        };
+        let totals: Vec<(String, AssetTotal)> = map
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        let next_start_after = next_cursor(&totals, limit);
+        Ok(TotalsResponse {
+            totals,
+            next_start_after,
+        })
+    }
+
+    fn query_pending_rewards(
+        &self,
+        deps: Deps,
+        env: Env,
+        address: String,
+        staked_token: String,
+    ) -> StdResult<PendingRewardsResponse> {
+        let staked = self
+            .cw20_deposits
+            .may_load(deps.storage, (&address, &staked_token))?
+            .map(|deposit| deposit.amount)
+            .unwrap_or_default();
+        let pools = self.accrued_pools(
+            deps.storage,
+            PoolKind::Cw20,
+            &staked_token,
+            env.block.height,
+        )?;
+        let mut rewards = vec![];
+        for (key, pool) in pools {
+            let mut stake = self
+                .reward_stakes
+                .may_load(deps.storage, (&staked_token, &key, &address))?
+                .unwrap_or_default();
+            settle_stake(&mut stake, &pool, staked)?;
+            rewards.push((pool.reward, stake.pending));
+        }
+        Ok(PendingRewardsResponse { rewards })
+    }
+
+    fn query_pending_nft_rewards(
+        &self,
+        deps: Deps,
+        env: Env,
+        owner: String,
+    ) -> StdResult<PendingRewardsResponse> {
+        let tokens: Vec<(String, String)> = self
+            .cw721_deposits
+            .idx
+            .owner
+            .prefix(owner)
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()?;
+
+        let mut rewards = vec![];
+        let mut pools: Vec<(String, RewardPool)> = vec![];
+        let mut collection = String::new();
+        for (token_collection, token_id) in tokens {
+            if token_collection != collection {
+                collection = token_collection;
+                pools = self.accrued_pools(
+                    deps.storage,
+                    PoolKind::Cw721,
+                    &collection,
+                    env.block.height,
+                )?;
+            }
+            for (_, reward, stake) in
+                self.settled_nft_stakes(deps.storage, &collection, &token_id, &pools)?
+            {
+                add_payout(&mut rewards, reward, stake.pending);
+            }
+        }
+        Ok(PendingRewardsResponse { rewards })
+    }
+
+    fn query_nft_reward_pools(
+        &self,
+        deps: Deps,
+        collection: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<RewardPoolsResponse> {
+        let limit = page_limit(limit);
+        let start = start_after.as_deref().map(Bound::exclusive);
+        let pools: Vec<(String, RewardPool)> = self
+            .nft_reward_pools
+            .prefix(&collection)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        let next_start_after = next_cursor(&pools, limit);
+        Ok(RewardPoolsResponse {
+            pools,
+            next_start_after,
+        })
+    }
+
+    fn query_reward_pools(
+        &self,
+        deps: Deps,
+        staked_token: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<RewardPoolsResponse> {
+        let limit = page_limit(limit);
+        let start = start_after.as_deref().map(Bound::exclusive);
+        let pools: Vec<(String, RewardPool)> = self
+            .reward_pools
+            .prefix(&staked_token)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        let next_start_after = next_cursor(&pools, limit);
+        Ok(RewardPoolsResponse {
+            pools,
+            next_start_after,
+        })
+    }
+
+    fn query_config(&self, deps: Deps) -> StdResult<ConfigResponse> {
+        let config = self.config.load(deps.storage)?;
+        Ok(ConfigResponse {
+            admin: config.admin.into_string(),
+            pending_admin: config.pending_admin.map(Addr::into_string),
+            default_lock_duration: config.default_lock_duration,
+            open: config.open,
+            early_withdraw: config.early_withdraw,
+            unbonding_period: config.unbonding_period,
+            marketplace_fee: config.marketplace_fee,
+        })
+    }
+
+    fn query_allowed_tokens(
+        &self,
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<AllowedTokensResponse> {
+        let limit = page_limit(limit);
+        let start = start_after.as_deref().map(Bound::exclusive);
+        let tokens: Vec<AllowedToken> = self
+            .allowed_tokens
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(contract, kind)| AllowedToken { contract, kind }))
+            .collect::<StdResult<_>>()?;
+        let next_start_after = if tokens.len() == limit {
+            tokens.last().map(|token| token.contract.clone())
+        } else {
+            None
+        };
+        Ok(AllowedTokensResponse {
+            tokens,
+            next_start_after,
+        })
+    }
+
+    fn query_lock_schedule(
+        &self,
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<LockScheduleResponse> {
+        let config = self.config.load(deps.storage)?;
+        let limit = page_limit(limit);
+        let start = start_after.as_deref().map(Bound::exclusive);
+        let cw20_lock_durations: Vec<Cw20LockDuration> = self
+            .cw20_lock_durations
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(contract, duration)| Cw20LockDuration { contract, duration }))
+            .collect::<StdResult<_>>()?;
+        let next_start_after = if cw20_lock_durations.len() == limit {
+            cw20_lock_durations.last().map(|lock| lock.contract.clone())
+        } else {
+            None
+        };
+        Ok(LockScheduleResponse {
+            default_lock_duration: config.default_lock_duration,
+            cw20_lock_durations,
+            next_start_after,
+        })
+    }
+
+    fn query_listings(
+        &self,
+        deps: Deps,
+        collection: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<ListingsResponse> {
+        let limit = page_limit(limit);
+        let start = start_after.as_deref().map(Bound::exclusive);
+        let listings: Vec<(String, Listing)> = self
+            .listings
+            .prefix(&collection)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        let next_start_after = next_cursor(&listings, limit);
+        Ok(ListingsResponse {
+            listings,
+            next_start_after,
+        })
+    }
+
+    fn query_auction(
+        &self,
+        deps: Deps,
+        env: Env,
+        contract: String,
+        token_id: String,
+    ) -> StdResult<AuctionResponse> {
+        let auction = self.auctions.load(deps.storage, (&contract, &token_id))?;
+        let min_bid = auction_min_bid(&auction, &env.block);
+        Ok(AuctionResponse { auction, min_bid })
+    }
+
+    fn query_auctions(
+        &self,
+        deps: Deps,
+        collection: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<AuctionsResponse> {
+        let limit = page_limit(limit);
+        let start = start_after.as_deref().map(Bound::exclusive);
+        let auctions: Vec<(String, Auction)> = self
+            .auctions
+            .prefix(&collection)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        let next_start_after = next_cursor(&auctions, limit);
+        Ok(AuctionsResponse {
+            auctions,
+            next_start_after,
+        })
+    }
+
+    fn query_auction_bids(
+        &self,
+        deps: Deps,
+        auction_id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<AuctionBidsResponse> {
+        let limit = page_limit(limit);
+        let start = start_after.map(Bound::exclusive);
+        let bids: Vec<(u64, AuctionBid)> = self
+            .auction_bids
+            .prefix(auction_id)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        let next_start_after = next_cursor(&bids, limit);
+        Ok(AuctionBidsResponse {
+            bids,
+            next_start_after,
+        })
+    }
+
+    fn query_offers(
+        &self,
+        deps: Deps,
+        contract: String,
+        token_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<OffersResponse> {
+        let limit = page_limit(limit);
+        let start = start_after.as_deref().map(Bound::exclusive);
+        let bids: Vec<(String, Offer)> = self
+            .offers
+            .prefix((&contract, &token_id))
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        let next_start_after = next_cursor(&bids, limit);
+        Ok(OffersResponse {
+            bids,
+            next_start_after,
+        })
+    }
+
+    fn query_balance(
+        &self,
+        deps: Deps,
+        address: String,
+        asset: AssetInfo,
+    ) -> StdResult<BalanceResponse> {
+        let amount = match &asset {
+            AssetInfo::Native { denom } => self
+                .deposits
+                .may_load(deps.storage, (&address, denom))?
+                .map(|deposit| deposit.coins.amount)
+                .unwrap_or_default(),
+            AssetInfo::Cw20 { contract } => self
+                .cw20_deposits
+                .may_load(deps.storage, (&address, contract))?
+                .map(|position| position.amount)
+                .unwrap_or_default(),
+            AssetInfo::Cw721 { contract, token_id } => match self
+                .cw721_deposits
+                .may_load(deps.storage, (contract, token_id))?
+            {
+                Some(deposit) if deposit.owner == address => Uint128::one(),
+                _ => Uint128::zero(),
+            },
+            AssetInfo::Cw1155 { contract, token_id } => self
+                .cw1155_deposits
+                .may_load(deps.storage, (contract, token_id, &address))?
+                .map(|deposit| deposit.amount)
+                .unwrap_or_default(),
+        };
+        Ok(BalanceResponse { asset, amount })
+    }
+
+    fn query_allowances(
+        &self,
+        deps: Deps,
+        owner: String,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    ) -> StdResult<AllowancesResponse> {
+        let limit = page_limit(limit);
+        let start = start_after
+            .as_ref()
+            .map(|(spender, key)| Bound::exclusive((spender.as_str(), key.as_str())));
+        let allowances: Vec<((String, String), Allowance)> = self
+            .allowances
+            .sub_prefix(&owner)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        let next_start_after = next_cursor(&allowances, limit);
+        Ok(AllowancesResponse {
+            allowances: allowances
+                .into_iter()
+                .map(|((spender, _), allowance)| (spender, allowance))
+                .collect(),
+            next_start_after,
+        })
+    }
+
+    fn query_claims(
+        &self,
+        deps: Deps,
+        address: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<ClaimsResponse> {
+        let limit = page_limit(limit);
+        let start = start_after.map(Bound::exclusive);
+        let claims: Vec<(u64, Cw20Claim)> = self
+            .cw20_claims
+            .prefix(&address)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        let next_start_after = next_cursor(&claims, limit);
+        Ok(ClaimsResponse {
+            claims,
+            next_start_after,
+        })
+    }
+
+    fn query_cw20_lots(
+        &self,
+        deps: Deps,
+        env: Env,
+        address: String,
+        contract: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<Cw20LotsResponse> {
+        //the position keeps its own total, locked and unlocked only split the page
+        let total = self
+            .cw20_deposits
+            .may_load(deps.storage, (&address, &contract))?
+            .map(|deposit| deposit.amount)
+            .unwrap_or_default();
+        let limit = page_limit(limit);
+        let start = start_after.map(Bound::exclusive);
+        let lots: Vec<(u64, Cw20Lot)> = self
+            .cw20_lots
+            .prefix((&address, &contract))
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?;
+        let (unlocked, locked) = lots.iter().fold(
+            (Uint128::zero(), Uint128::zero()),
+            |(unlocked, locked), (_, lot)| {
+                if lot.stake_time.is_expired(&env.block) {
+                    (unlocked + lot.amount, locked)
+                } else {
+                    (unlocked, locked + lot.amount)
+                }
+            },
+        );
+        let next_start_after = next_cursor(&lots, limit);
+        Ok(Cw20LotsResponse {
+            lots,
+            total,
+            locked,
+            unlocked,
+            next_start_after,
+        })
+    }
+
+    fn query_total_cw20_deposits(
+        &self,
+        deps: Deps,
+        env: Env,
+        height: Option<u64>,
+    ) -> StdResult<TotalCw20DepositsResponse> {
+        let (total, height) = match height {
+            //snapshots hold the value as it was at the start of a block
+            Some(height) => (
+                self.total_cw20_deposits
+                    .may_load_at_height(deps.storage, height)?,
+                height,
+            ),
+            None => (
+                self.total_cw20_deposits.may_load(deps.storage)?,
+                env.block.height,
+            ),
+        };
+        Ok(TotalCw20DepositsResponse {
+            total: total.unwrap_or_default(),
+            height,
+        })
+    }
+
+    fn query_total_cw20_deposits_changelog(
+        &self,
+        deps: Deps,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<TotalCw20DepositsChangelogResponse> {
+        let limit = page_limit(limit);
+        let start = start_after.map(Bound::exclusive);
+        let changelog: Vec<(u64, Option<u64>)> = self
+            .total_cw20_deposits
+            .changelog()
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(height, change)| (height, change.old)))
+            .collect::<StdResult<_>>()?;
+        let next_start_after = next_cursor(&changelog, limit);
+        Ok(TotalCw20DepositsChangelogResponse {
+            changelog,
+            next_start_after,
+        })
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    let contract = Deposit::<Empty>::default();
+    match msg {
+        QueryMsg::Deposits {
+            address,
+            start_after,
+            limit,
+        } => to_binary(&contract.query_deposits(deps, address, start_after, limit)?),
+        QueryMsg::DepositsAt {
+            address,
+            height,
+            start_after,
+            limit,
+        } => to_binary(&contract.query_deposits_at(deps, address, height, start_after, limit)?),
+        QueryMsg::Cw20DepositsAt {
+            address,
+            height,
+            start_after,
+            limit,
+        } => to_binary(&contract.query_cw20_deposits_at(
+            deps,
+            address,
+            height,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::Cw20Deposits {
+            address,
+            start_after,
+            limit,
+        } => to_binary(&contract.query_cw20_deposits(deps, address, start_after, limit)?),
         QueryMsg::Cw721DepositsByContract {
             contract_addr,
-        } => to_binary(&contract.query_cw721_by_contract(deps, contract_addr)?),
-        QueryMsg::Cw721DepositsByOwner { address } => {
-            to_binary(&contract.query_cw721_by_owner(deps, address)?)
+            start_after,
+            limit,
+        } => {
+            to_binary(&contract.query_cw721_by_contract(deps, contract_addr, start_after, limit)?)
+        }
+        QueryMsg::Cw721DepositsByOwner {
+            address,
+            start_after,
+            limit,
+        } => to_binary(&contract.query_cw721_by_owner(deps, address, start_after, limit)?),
+        QueryMsg::Cw721DepositChangelog {
+            contract_addr,
+            token_id,
+            start_after,
+            limit,
+        } => to_binary(&contract.query_cw721_deposit_changelog(
+            deps,
+            contract_addr,
+            token_id,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::Cw721DepositAt {
+            contract_addr,
+            token_id,
+            height,
+        } => to_binary(&contract.query_cw721_deposit_at(deps, contract_addr, token_id, height)?),
+        QueryMsg::Cw721DepositsByOwnerAt {
+            address,
+            height,
+            start_after,
+            limit,
+        } => to_binary(&contract.query_cw721_by_owner_at(
+            deps,
+            address,
+            height,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::Cw721Operators {
+            address,
+            start_after,
+            limit,
+        } => to_binary(&contract.query_cw721_operators(deps, address, start_after, limit)?),
+        QueryMsg::Cw1155DepositsByContract {
+            contract_addr,
+            start_after,
+            limit,
+        } => to_binary(&contract.query_cw1155_by_contract(
+            deps,
+            contract_addr,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::Cw1155DepositsByOwner {
+            address,
+            start_after,
+            limit,
+        } => to_binary(&contract.query_cw1155_by_owner(deps, address, start_after, limit)?),
+        QueryMsg::Allowances {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&contract.query_allowances(deps, owner, start_after, limit)?),
+        QueryMsg::Balance { address, asset } => {
+            to_binary(&contract.query_balance(deps, address, asset)?)
+        }
+        QueryMsg::Claims {
+            address,
+            start_after,
+            limit,
+        } => to_binary(&contract.query_claims(deps, address, start_after, limit)?),
+        QueryMsg::Cw20Lots {
+            address,
+            contract: cw20_contract,
+            start_after,
+            limit,
+        } => to_binary(&contract.query_cw20_lots(
+            deps,
+            env,
+            address,
+            cw20_contract,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::LockSchedule { start_after, limit } => {
+            to_binary(&contract.query_lock_schedule(deps, start_after, limit)?)
+        }
+        QueryMsg::TotalCw20Deposits { height } => {
+            to_binary(&contract.query_total_cw20_deposits(deps, env, height)?)
+        }
+        QueryMsg::TotalCw20DepositsChangelog { start_after, limit } => {
+            to_binary(&contract.query_total_cw20_deposits_changelog(deps, start_after, limit)?)
+        }
+        QueryMsg::Config {} => to_binary(&contract.query_config(deps)?),
+        QueryMsg::Totals {
+            kind,
+            start_after,
+            limit,
+        } => to_binary(&contract.query_totals(deps, kind, start_after, limit)?),
+        QueryMsg::PendingRewards {
+            address,
+            staked_token,
+        } => to_binary(&contract.query_pending_rewards(deps, env, address, staked_token)?),
+        QueryMsg::PendingNftRewards { owner } => {
+            to_binary(&contract.query_pending_nft_rewards(deps, env, owner)?)
+        }
+        QueryMsg::NftRewardPools {
+            collection,
+            start_after,
+            limit,
+        } => to_binary(&contract.query_nft_reward_pools(deps, collection, start_after, limit)?),
+        QueryMsg::RewardPools {
+            staked_token,
+            start_after,
+            limit,
+        } => to_binary(&contract.query_reward_pools(deps, staked_token, start_after, limit)?),
+        QueryMsg::AllowedTokens { start_after, limit } => {
+            to_binary(&contract.query_allowed_tokens(deps, start_after, limit)?)
+        }
+        QueryMsg::Listings {
+            collection,
+            start_after,
+            limit,
+        } => to_binary(&contract.query_listings(deps, collection, start_after, limit)?),
+        QueryMsg::Auction {
+            contract_addr,
+            token_id,
+        } => to_binary(&contract.query_auction(deps, env, contract_addr, token_id)?),
+        QueryMsg::Auctions {
+            collection,
+            start_after,
+            limit,
+        } => to_binary(&contract.query_auctions(deps, collection, start_after, limit)?),
+        QueryMsg::AuctionBids {
+            auction_id,
+            start_after,
+            limit,
+        } => to_binary(&contract.query_auction_bids(deps, auction_id, start_after, limit)?),
+        QueryMsg::Offers {
+            contract_addr,
+            token_id,
+            start_after,
+            limit,
+        } => {
+            to_binary(&contract.query_offers(deps, contract_addr, token_id, start_after, limit)?)
         }
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    unimplemented!()
+pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::CannotMigrate {
+            previous_contract: stored.contract,
+        });
+    }
+
+    let previous_version = parse_version(&stored.version)?;
+    let new_version = parse_version(CONTRACT_VERSION)?;
+    if previous_version > new_version {
+        return Err(ContractError::CannotMigrateVersion {
+            previous_version: stored.version,
+            new_version: CONTRACT_VERSION.to_string(),
+        });
+    }
+
+    //migrations run in release order, each one only when the stored version predates it
+    let contract = Deposit::<Empty>::default();
+    if previous_version < Version::new(0, 2, 0) {
+        migrations::v0_2_0(deps.branch(), &env, &contract, &msg)?;
+    }
+    if previous_version < Version::new(0, 3, 0) {
+        migrations::v0_3_0(deps.branch(), &env, &contract)?;
+    }
+    if previous_version < Version::new(0, 4, 0) {
+        migrations::v0_4_0(deps.branch(), &contract)?;
+    }
+    if previous_version < Version::new(0, 5, 0) {
+        migrations::v0_5_0(deps.branch(), &contract)?;
+    }
+    if previous_version < Version::new(0, 6, 0) {
+        migrations::v0_6_0(deps.branch(), &contract)?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("execute", "migrate")
+        .add_attribute("previous_version", stored.version)
+        .add_attribute("new_version", CONTRACT_VERSION))
+}
+
+fn page_limit(limit: Option<u32>) -> usize {
+    limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize
+}
+
+//a full page means there may be more, so hand back the last key to continue from
+fn next_cursor<K: Clone, T>(items: &[(K, T)], limit: usize) -> Option<K> {
+    if items.len() == limit {
+        items.last().map(|(key, _)| key.clone())
+    } else {
+        None
+    }
+}
+
+//cursor for pages that look at limit keys but may return fewer items
+fn page_cursor<K: Clone>(keys: &[K], limit: usize) -> Option<K> {
+    if keys.len() == limit {
+        keys.last().cloned()
+    } else {
+        None
+    }
+}
+
+//emits the rewards of the blocks since the last update across all shares,
+//blocks without shares emit nothing and the rewards stay in the pool
+fn accrue_pool(
+    pool: &mut RewardPool,
+    shares: Uint128,
+    emission_per_block: Uint128,
+    height: u64,
+) -> StdResult<()> {
+    if height <= pool.last_update_height {
+        return Ok(());
+    }
+    if !shares.is_zero() {
+        let blocks = Uint128::from(height - pool.last_update_height);
+        let emitted = emission_per_block.checked_mul(blocks)?.min(pool.balance);
+        pool.balance -= emitted;
+        pool.reward_per_share = pool
+            .reward_per_share
+            .checked_add(Decimal256::from_ratio(emitted, shares))?;
+    }
+    pool.last_update_height = height;
+    Ok(())
+}
+
+//max_penalty right after the deposit, falling linearly to nothing at expiry
+fn lot_penalty(lot: &Cw20Lot, taken: Uint128, max_penalty: Decimal, block: &BlockInfo) -> Uint128 {
+    if lot.stake_time.is_expired(block) {
+        return Uint128::zero();
+    }
+    let (left, total) = match (lot.stake_time, lot.lock_duration) {
+        (Expiration::AtHeight(end), Duration::Height(total)) => (end - block.height, total),
+        (Expiration::AtTime(end), Duration::Time(total)) => {
+            (end.seconds().saturating_sub(block.time.seconds()), total)
+        }
+        //never expires or the units do not match, nothing to decay
+        _ => (1, 1),
+    };
+    let share = if total == 0 {
+        Decimal::one()
+    } else {
+        Decimal::from_ratio(left.min(total), total)
+    };
+    taken * (max_penalty * share)
+}
+
+fn reward_msg<C>(reward: RewardToken, recipient: &str, amount: Uint128) -> StdResult<CosmosMsg<C>>
+where
+    C: CustomMsg,
+{
+    Ok(match reward {
+        RewardToken::Native { denom } => BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![coin(amount.u128(), denom)],
+        }
+        .into(),
+        RewardToken::Cw20 { contract } => WasmMsg::Execute {
+            contract_addr: contract,
+            msg: to_binary(&cw20_base::msg::ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }
+        .into(),
+    })
+}
+
+//lowest bid the auction takes at block. dutch prices fall linearly over the
+//auction, by height or time depending on how it was started
+fn auction_min_bid(auction: &Auction, block: &BlockInfo) -> Uint128 {
+    match &auction.kind {
+        AuctionKind::English { min_increment, .. } => match &auction.highest_bid {
+            Some(bid) => bid.amount.saturating_add(*min_increment),
+            None => auction.start_price,
+        },
+        AuctionKind::Dutch { end_price } => {
+            let (elapsed, length) = match (auction.start, auction.end) {
+                (Expiration::AtHeight(start), Expiration::AtHeight(end)) => {
+                    (block.height.saturating_sub(start), end - start)
+                }
+                (Expiration::AtTime(start), Expiration::AtTime(end)) => (
+                    block.time.seconds().saturating_sub(start.seconds()),
+                    end.seconds() - start.seconds(),
+                ),
+                _ => return *end_price,
+            };
+            let drop =
+                (auction.start_price - *end_price).multiply_ratio(elapsed.min(length), length);
+            auction.start_price - drop
+        }
+    }
+}
+
+//pays amount of asset out to recipient. with a msg it is sent into the recipient contract,
+//cw20 through Send and nfts through SendNft or SendFrom. natives are only paid with a
+//BankMsg, an execute would run any msg the withdrawer picks as this contract.
+//holder is this contract, which cw1155 moves tokens out of by address
+fn asset_msg<C>(
+    holder: &Addr,
+    asset: &AssetInfo,
+    amount: Uint128,
+    recipient: &str,
+    msg: Option<Binary>,
+) -> Result<CosmosMsg<C>, ContractError>
+where
+    C: CustomMsg,
+{
+    Ok(match asset {
+        AssetInfo::Native { denom } => {
+            if msg.is_some() {
+                return Err(ContractError::NativeMsgUnsupported {});
+            }
+            BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![coin(amount.u128(), denom)],
+            }
+            .into()
+        }
+        AssetInfo::Cw20 { contract } => {
+            let exe_msg = match msg {
+                Some(msg) => cw20_base::msg::ExecuteMsg::Send {
+                    contract: recipient.to_string(),
+                    amount,
+                    msg,
+                },
+                None => cw20_base::msg::ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount,
+                },
+            };
+            WasmMsg::Execute {
+                contract_addr: contract.clone(),
+                msg: to_binary(&exe_msg)?,
+                funds: vec![],
+            }
+            .into()
+        }
+        AssetInfo::Cw721 { contract, token_id } => {
+            let exe_msg = match msg {
+                Some(msg) => nft::contract::ExecuteMsg::SendNft {
+                    contract: recipient.to_string(),
+                    token_id: token_id.clone(),
+                    msg,
+                },
+                None => nft::contract::ExecuteMsg::TransferNft {
+                    recipient: recipient.to_string(),
+                    token_id: token_id.clone(),
+                },
+            };
+            WasmMsg::Execute {
+                contract_addr: contract.clone(),
+                msg: to_binary(&exe_msg)?,
+                funds: vec![],
+            }
+            .into()
+        }
+        AssetInfo::Cw1155 { contract, token_id } => WasmMsg::Execute {
+            contract_addr: contract.clone(),
+            msg: to_binary(&Cw1155ExecuteMsg::SendFrom {
+                from: holder.to_string(),
+                to: recipient.to_string(),
+                token_id: token_id.clone(),
+                value: amount,
+                msg,
+            })?,
+            funds: vec![],
+        }
+        .into(),
+    })
+}
+
+//an nft is held once, so it only moves with an amount of one
+fn assert_single_nft(amount: Uint128) -> Result<(), ContractError> {
+    if amount != Uint128::one() {
+        return Err(ContractError::InvalidNftAmount {});
+    }
+    Ok(())
+}
+
+//adds amount to the entry of reward, payouts from several pools go out as one message per token
+fn add_payout(payouts: &mut Vec<(RewardToken, Uint128)>, reward: RewardToken, amount: Uint128) {
+    if amount.is_zero() {
+        return;
+    }
+    match payouts.iter_mut().find(|(r, _)| r == &reward) {
+        Some((_, total)) => *total += amount,
+        None => payouts.push((reward, amount)),
+    }
+}
+
+//moves what staked earned since the last settlement into pending
+fn settle_stake(stake: &mut RewardStake, pool: &RewardPool, staked: Uint128) -> StdResult<()> {
+    let earned = Uint256::from(staked) * (pool.reward_per_share - stake.reward_per_share_paid);
+    let earned = Uint128::try_from(earned).map_err(|e| StdError::generic_err(e.to_string()))?;
+    stake.pending = stake.pending.checked_add(earned)?;
+    stake.reward_per_share_paid = pool.reward_per_share;
+    Ok(())
+}
+
+//keeps an asset total in step with one owner's balance moving from before to after
+fn update_total(
+    storage: &mut dyn Storage,
+    totals: &Map<&str, AssetTotal>,
+    asset: &str,
+    before: Uint128,
+    after: Uint128,
+) -> StdResult<()> {
+    let mut total = totals.may_load(storage, asset)?.unwrap_or_default();
+    total.amount = total.amount.checked_add(after)?.checked_sub(before)?;
+    if before.is_zero() && !after.is_zero() {
+        total.depositors += 1;
+    } else if !before.is_zero() && after.is_zero() {
+        total.depositors = total.depositors.saturating_sub(1);
+    }
+    totals.save(storage, asset, &total)
+}
+
+fn parse_version(version: &str) -> Result<Version, ContractError> {
+    Version::parse(version).map_err(|e| ContractError::CustomError {
+        val: format!("Invalid version {}: {}", version, e),
+    })
 }
 
 pub fn receive_cw20(
@@ -391,8 +4132,50 @@ pub fn receive_cw20(
 ) -> Result<Response, ContractError> {
     match from_binary(&cw20_msg.msg) {
         Ok(Cw20HookMsg::Deposit {}) => {
+            contract.assert_allowed_token(deps.storage, &info.sender, TokenKind::Cw20)?;
             contract.execute_cw20_deposit(deps, env, info, cw20_msg.sender, cw20_msg.amount)
         }
+        //reward tokens are chosen by the admin through SetRewardPool
+        Ok(Cw20HookMsg::FundRewards { staked_token }) => {
+            contract.execute_cw20_fund_rewards(deps, env, info, staked_token, cw20_msg.amount)
+        }
+        Ok(Cw20HookMsg::FundNftRewards { collection }) => {
+            contract.execute_cw20_fund_nft_rewards(deps, env, info, collection, cw20_msg.amount)
+        }
+        Ok(Cw20HookMsg::Buy {
+            contract_addr,
+            token_id,
+        }) => {
+            let payment = AssetInfo::Cw20 {
+                contract: info.sender.into_string(),
+            };
+            contract.execute_buy_nft(
+                deps,
+                env,
+                cw20_msg.sender,
+                contract_addr,
+                token_id,
+                payment,
+                cw20_msg.amount,
+            )
+        }
+        Ok(Cw20HookMsg::BidAuction {
+            contract_addr,
+            token_id,
+        }) => {
+            let payment = AssetInfo::Cw20 {
+                contract: info.sender.into_string(),
+            };
+            contract.execute_bid_auction(
+                deps,
+                env,
+                cw20_msg.sender,
+                contract_addr,
+                token_id,
+                payment,
+                cw20_msg.amount,
+            )
+        }
         _ => Err(ContractError::CustomError {
             val: "Invalid Cw20HookMsg".to_string(),
         }),
@@ -406,6 +4189,7 @@ pub fn receive_cw721(
     contract: &Deposit<Empty>,
     cw721_msg: Cw721ReceiveMsg,
 ) -> Result<Response, ContractError> {
+    contract.assert_allowed_token(deps.storage, &info.sender, TokenKind::Cw721)?;
     match from_binary(&cw721_msg.msg) {
         Ok(Cw721HookMsg::Deposit {}) => {
             contract.execute_cw721_deposit(deps, env, info, cw721_msg.sender, cw721_msg.token_id)
@@ -415,3 +4199,23 @@ pub fn receive_cw721(
         }),
     }
 }
+
+pub fn receive_cw1155(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract: &Deposit<Empty>,
+    cw1155_msg: Cw1155BatchReceiveMsg,
+) -> Result<Response, ContractError> {
+    contract.assert_allowed_token(deps.storage, &info.sender, TokenKind::Cw1155)?;
+    match from_binary(&cw1155_msg.msg) {
+        Ok(Cw1155HookMsg::Deposit {}) => {
+            //tokens minted straight into the contract belong to whoever minted them
+            let owner = cw1155_msg.from.unwrap_or(cw1155_msg.operator);
+            contract.execute_cw1155_deposit(deps, env, info, owner, cw1155_msg.batch)
+        }
+        _ => Err(ContractError::CustomError {
+            val: "Invalid Cw1155HookMsg".to_string(),
+        }),
+    }
+}
@@ -1,27 +1,159 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{OverflowError, StdError, Uint128};
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
+    #[error("Cannot migrate from contract {previous_contract}")]
+    CannotMigrate { previous_contract: String },
+
+    #[error("Cannot migrate from version {previous_version} to {new_version}")]
+    CannotMigrateVersion {
+        previous_version: String,
+        new_version: String,
+    },
+
+    #[error("Migration option {option} is required")]
+    MigrationOptionRequired { option: String },
+
     #[error("Custom Error val: {val:?}")]
     CustomError { val: String },
 
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("No admin transfer has been proposed")]
+    NoPendingAdmin {},
+
+    #[error("Token {contract} is not allowed")]
+    TokenNotAllowed { contract: String },
+
     #[error("Invalid Owner")]
     InvalidOwner {},
 
+    #[error("Operator approval already expired")]
+    Expired {},
+
     #[error("Invalid Coin")]
     InvalidCoin {},
 
     #[error("Stake duration has not passed")]
     StakeDurationNotPassed {},
 
-    #[error("User does not have coins from this cw20 to withdraw")]
-    NoCw20ToWithdraw {},
+    #[error("Insufficient balance of {asset}: available {available}, requested {requested}")]
+    InsufficientBalance {
+        asset: String,
+        available: Uint128,
+        requested: Uint128,
+    },
+
+    #[error("No deposit of denom {denom}")]
+    UnknownDenom { denom: String },
+
+    #[error("{owner} has no deposit of cw20 {contract}")]
+    UnknownCw20Position { owner: String, contract: String },
+
+    #[error("{owner} has no deposit of token {token_id} of cw1155 {contract}")]
+    UnknownCw1155Balance { owner: String, contract: String, token_id: String },
+
+    #[error("Batch must contain at least one token")]
+    EmptyBatch {},
+
+    #[error("Token {token_id} of cw721 {contract} is not deposited")]
+    NftNotDeposited { contract: String, token_id: String },
+
+    #[error("Cannot bid on own token")]
+    CannotBidOnOwnToken {},
+
+    #[error("{bidder} has no bid on this token")]
+    NoBid { bidder: String },
+
+    #[error("Fee must be between 0 and 1")]
+    InvalidFee {},
+
+    #[error("Nfts can only be sold for a native denom or a cw20")]
+    InvalidPaymentAsset {},
+
+    #[error("Token {token_id} of cw721 {contract} is not listed")]
+    ListingNotFound { contract: String, token_id: String },
+
+    #[error("Token {token_id} of cw721 {contract} is being auctioned")]
+    NftInAuction { contract: String, token_id: String },
+
+    #[error("Token {token_id} of cw721 {contract} is not auctioned")]
+    AuctionNotFound { contract: String, token_id: String },
+
+    #[error("Auction duration must be nonzero and the extension of the same kind")]
+    InvalidAuctionDuration {},
+
+    #[error("Dutch auctions must end below their start price")]
+    InvalidDutchPrices {},
+
+    #[error("Auction has ended")]
+    AuctionEnded {},
+
+    #[error("Auction has not ended yet")]
+    AuctionNotEnded {},
+
+    #[error("Auction already has bids")]
+    AuctionHasBids {},
+
+    #[error("Bid must be at least {min}")]
+    BidTooLow { min: Uint128 },
+
+    #[error("Payment must be exactly {expected}")]
+    IncorrectPayment { expected: String },
 
     #[error("Contract does not possess token_id from this cw721 to withdraw")]
     NoCw721ToWithdraw {},
 
+    #[error("No reward pool of {reward} for staked cw20 {staked_token}")]
+    RewardPoolNotFound { staked_token: String, reward: String },
+
+    #[error("No reward pool of {reward} for cw721 {collection}")]
+    NftRewardPoolNotFound { collection: String, reward: String },
+
+    #[error("No rewards to claim")]
+    NoRewardsToClaim {},
+
+    #[error("Early withdrawals are disabled")]
+    EarlyWithdrawDisabled {},
+
+    #[error("Penalty must be between 0 and 1")]
+    InvalidPenalty {},
+
+    #[error("Amount must be greater than zero")]
+    ZeroAmount {},
+
+    #[error("An nft can only be moved with an amount of one")]
+    InvalidNftAmount {},
+
+    #[error("Cannot set an allowance to own account")]
+    CannotSetOwnAccount {},
+
+    #[error("Allowance expiration is already expired")]
+    InvalidExpiration {},
+
+    #[error("No allowance for this account")]
+    NoAllowance {},
+
+    #[error("Allowance is expired")]
+    AllowanceExpired {},
+
+    #[error("Insufficient allowance: available {available}, requested {requested}")]
+    InsufficientAllowance { available: Uint128, requested: Uint128 },
+
+    #[error("Cw20 has to be unbonded and claimed once the unbonding period is over")]
+    UnbondingRequired {},
+
+    #[error("No unbonded cw20 ready to claim")]
+    NoMaturedClaims {},
+
+    #[error("Native coins cannot be withdrawn with a msg")]
+    NativeMsgUnsupported {},
 }
@@ -1,17 +1,21 @@
 #[cfg(test)]
 mod tests {
     use crate::helpers::DepositContract;
-    use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, Cw20HookMsg, Cw20DepositResponse, DepositResponse, Cw721DepositResponse, Cw721HookMsg};
-    use cosmwasm_std::{Addr, Coin, Empty, Uint128, to_binary, BankQuery, BankMsg, coin, WasmMsg};
+    use crate::msg::{AllowancesResponse, AssetKind, ExecuteMsg, InstantiateMsg, QueryMsg, Cw20HookMsg, Cw20DepositResponse, DepositResponse, Cw721DepositResponse, Cw721DepositsByOwnerResponse, Cw721HookMsg, Cw721DepositChangelogResponse, Cw721DepositAtResponse, Cw1155DepositResponse, Cw1155DepositsByOwnerResponse, Cw1155HookMsg, AuctionBidsResponse, AuctionResponse, AuctionsResponse, ListingsResponse, OffersResponse, Cw20LockDuration, LockScheduleResponse, Cw20LotsResponse, ConfigResponse, ClaimsResponse, TotalsResponse, PendingRewardsResponse, RewardPoolsResponse, TotalCw20DepositsResponse, TotalCw20DepositsChangelogResponse, AllowedToken, AllowedTokensResponse, MigrateMsg};
+    use crate::state::{Allowance, AssetTotal, AuctionBid, AuctionKind, Cw20Claim, AssetInfo, EarlyWithdraw, MarketplaceFee, Offer, PenaltyRecipient, RewardToken, TokenKind};
+    use crate::ContractError;
+    use cosmwasm_std::{Addr, Coin, Decimal, Empty, OverflowError, OverflowOperation, Uint128, to_binary, coin, WasmMsg};
     use cw20::{Cw20Contract, Cw20Coin, BalanceResponse};
     use cw20_base::msg::ExecuteMsg as Cw20ExecuteMsg;
     use cw20_base::msg::InstantiateMsg as Cw20InstantiateMsg;
     use cw20_base::msg::QueryMsg as Cw20QueryMsg;
     use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
+    use cw_utils::{Duration, Expiration};
 
     use cw20_example::{self};
 
     use cw721::OwnerOfResponse;
+    use cw1155::Cw1155ExecuteMsg;
     use nft::helpers::NftContract;
     use nft::{self};
 
@@ -20,7 +24,142 @@ mod tests {
             crate::contract::execute,
             crate::contract::instantiate,
             crate::contract::query,
-        );
+        )
+        .with_migrate(crate::contract::migrate);
+        Box::new(contract)
+    }
+
+    //stand-in for a deployed v0.1.0 instance, writes state in the old layout
+    mod legacy {
+        use cosmwasm_std::{coin, Addr, BankMsg, Binary, Coin, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdError, StdResult, Uint128};
+        use cw20::Cw20ReceiveMsg;
+        use cw_storage_plus::{Index, IndexList, IndexedMap, Map, MultiIndex};
+        use cw_utils::Expiration;
+        use schemars::JsonSchema;
+        use serde::{Deserialize, Serialize};
+
+        use crate::state::{Cw20Deposits, Cw721Deposits, Deposit};
+
+        #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+        pub struct LegacyInstantiateMsg {
+            pub contract: String,
+            pub version: String,
+        }
+
+        #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+        #[serde(rename_all = "snake_case")]
+        pub enum LegacyExecuteMsg {
+            Deposit {},
+            Withdraw { amount: u128, denom: String },
+            Receive(Cw20ReceiveMsg),
+            //nft custody as v0.5.0 recorded it, None takes the nft out
+            Cw721Custody { owner: Option<String>, contract: String, token_id: String },
+        }
+
+        #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+        struct LegacyDeposits {
+            count: i32,
+            owner: Addr,
+            coins: Coin,
+        }
+
+        const DEPOSITS: Map<(&str, &str), LegacyDeposits> = Map::new("deposits");
+
+        //v0.1.0 kept cw20 positions in an IndexedMap without a changelog
+        struct LegacyCw20Indexes<'a> {
+            count: MultiIndex<'a, u64, Cw20Deposits, (&'a str, &'a str)>,
+            owner: MultiIndex<'a, String, Cw20Deposits, (&'a str, &'a str)>,
+        }
+
+        impl<'a> IndexList<Cw20Deposits> for LegacyCw20Indexes<'a> {
+            fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Cw20Deposits>> + '_> {
+                let v: Vec<&dyn Index<Cw20Deposits>> = vec![&self.count, &self.owner];
+                Box::new(v.into_iter())
+            }
+        }
+
+        fn cw20_deposits<'a>() -> IndexedMap<'a, (&'a str, &'a str), Cw20Deposits, LegacyCw20Indexes<'a>> {
+            IndexedMap::new(
+                "cw20_deposits",
+                LegacyCw20Indexes {
+                    count: MultiIndex::new(|_pk, d| d.count, "cw20deposits", "cw20deposits__count"),
+                    owner: MultiIndex::new(|_pk, d| d.owner.clone(), "cw20deposits", "cw20deposits__owner"),
+                },
+            )
+        }
+
+        pub fn instantiate(deps: DepsMut, _env: Env, _info: MessageInfo, msg: LegacyInstantiateMsg) -> StdResult<Response> {
+            cw2::set_contract_version(deps.storage, msg.contract, msg.version)?;
+            Ok(Response::new())
+        }
+
+        pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: LegacyExecuteMsg) -> StdResult<Response> {
+            match msg {
+                LegacyExecuteMsg::Deposit {} => {
+                    let coins = info.funds[0].clone();
+                    let key = (info.sender.as_str(), coins.denom.as_str());
+                    let deposit = match DEPOSITS.may_load(deps.storage, key)? {
+                        Some(mut deposit) => {
+                            deposit.coins.amount += coins.amount;
+                            deposit.count += 1;
+                            deposit
+                        }
+                        None => LegacyDeposits { count: 1, owner: info.sender.clone(), coins: coins.clone() },
+                    };
+                    DEPOSITS.save(deps.storage, key, &deposit)?;
+                }
+                //v0.1.0 counted withdrawals down without a floor, so counts go negative
+                LegacyExecuteMsg::Withdraw { amount, denom } => {
+                    let key = (info.sender.as_str(), denom.as_str());
+                    let mut deposit = DEPOSITS.load(deps.storage, key)?;
+                    deposit.coins.amount -= Uint128::new(amount);
+                    deposit.count -= 1;
+                    DEPOSITS.save(deps.storage, key, &deposit)?;
+                    let msg = BankMsg::Send { to_address: info.sender.to_string(), amount: vec![coin(amount, denom)] };
+                    return Ok(Response::new().add_message(msg));
+                }
+                LegacyExecuteMsg::Receive(cw20_msg) => {
+                    let key = (cw20_msg.sender.as_str(), info.sender.as_str());
+                    let deposit = match cw20_deposits().may_load(deps.storage, key)? {
+                        Some(mut deposit) => {
+                            deposit.amount += cw20_msg.amount;
+                            deposit.count += 1;
+                            deposit.stake_time = Expiration::AtHeight(env.block.height + 20);
+                            deposit
+                        }
+                        None => Cw20Deposits {
+                            count: 1,
+                            owner: cw20_msg.sender.clone(),
+                            contract: info.sender.to_string(),
+                            amount: cw20_msg.amount,
+                            stake_time: Expiration::AtHeight(env.block.height + 20),
+                        },
+                    };
+                    cw20_deposits().save(deps.storage, key, &deposit)?;
+                    let contract = Deposit::<Empty>::default();
+                    contract.total_cw20_deposits.update(deps.storage, env.block.height, |total| -> StdResult<u64> { Ok(total.unwrap_or_default() + 1) })?;
+                }
+                LegacyExecuteMsg::Cw721Custody { owner, contract: collection, token_id } => {
+                    let contract = Deposit::<Empty>::default();
+                    match owner {
+                        Some(owner) => {
+                            let nft = Cw721Deposits { owner, contract: collection.clone(), token_id: token_id.clone() };
+                            contract.cw721_deposits.save(deps.storage, (&collection, &token_id), &nft, env.block.height)?;
+                        }
+                        None => contract.cw721_deposits.remove(deps.storage, (&collection, &token_id), env.block.height)?,
+                    }
+                }
+            }
+            Ok(Response::new())
+        }
+
+        pub fn query(_deps: Deps, _env: Env, _msg: Binary) -> StdResult<Binary> {
+            Err(StdError::generic_err("legacy contract has no queries"))
+        }
+    }
+
+    pub fn contract_legacy_deposit() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(legacy::execute, legacy::instantiate, legacy::query);
         Box::new(contract)
     }
 
@@ -42,9 +181,21 @@ mod tests {
         Box::new(contract)
     }
 
+    //passes straight through to cw1155-base, which calls receivers with receive and batch_receive
+    pub fn contract_cw1155() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            cw1155_example::contract::execute,
+            cw1155_example::contract::instantiate,
+            cw1155_example::contract::query,
+        );
+        Box::new(contract)
+    }
+
     const USER: &str = "juno10c3slrqx3369mfsr9670au22zvq082jaej8ve4";
+    const OTHER_USER: &str = "juno1ylf9hy9zvm2m6x0z5ltvmu8wgkdaeq6vmkh6ss";
     const ADMIN: &str = "ADMIN";
     const NATIVE_DENOM: &str = "denom";
+    const OTHER_DENOM: &str = "other";
 
     fn mock_app() -> App {
         AppBuilder::new().build(|router, _, storage| {
@@ -53,10 +204,16 @@ mod tests {
                 .init_balance(
                     storage,
                     &Addr::unchecked(USER),
-                    vec![Coin {
-                        denom: NATIVE_DENOM.to_string(),
-                        amount: Uint128::new(1000),
-                    }],
+                    vec![
+                        Coin {
+                            denom: NATIVE_DENOM.to_string(),
+                            amount: Uint128::new(1000),
+                        },
+                        Coin {
+                            denom: OTHER_DENOM.to_string(),
+                            amount: Uint128::new(1000),
+                        },
+                    ],
                 )
                 .unwrap();
         })
@@ -71,7 +228,11 @@ mod tests {
     }
 
     fn deposit_instantiate(app: &mut App, deposit_id: u64) -> DepositContract {
-        let msg = InstantiateMsg {};
+        let msg = InstantiateMsg { admin: None, default_lock_duration: None, cw20_lock_durations: vec![], open: true, allowed_tokens: vec![] };
+        deposit_instantiate_with_msg(app, deposit_id, msg)
+    }
+
+    fn deposit_instantiate_with_msg(app: &mut App, deposit_id: u64, msg: InstantiateMsg) -> DepositContract {
         let deposit_contract_address = app
             .instantiate_contract(
                 deposit_id,
@@ -117,7 +278,7 @@ mod tests {
 
     fn get_deposits(app: &App, deposit_contract: &DepositContract) -> DepositResponse {
         app.wrap()
-            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Deposits { address: USER.to_string() })
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Deposits { address: USER.to_string(), start_after: None, limit: None })
             .unwrap()
     }
 
@@ -127,7 +288,7 @@ mod tests {
 
     fn get_cw20_deposits(app: &App, deposit_contract: &DepositContract) -> Cw20DepositResponse {
         app.wrap()
-            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Cw20Deposits { address: USER.to_string() })
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Cw20Deposits { address: USER.to_string(), start_after: None, limit: None })
             .unwrap()
     }
 
@@ -139,13 +300,82 @@ mod tests {
 
     fn get_cw721_deposits_by_contract(app: &App, deposit_contract: &DepositContract, nft_contract:&NftContract) -> Cw721DepositResponse {
         app.wrap()
-            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Cw721DepositsByContract { contract_addr: nft_contract.addr().to_string() })
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Cw721DepositsByContract { contract_addr: nft_contract.addr().to_string(), start_after: None, limit: None })
+            .unwrap()
+    }
+
+    fn get_cw721_deposits_by_owner(app: &App, deposit_contract: &DepositContract, owner:String) -> Cw721DepositsByOwnerResponse {
+        get_cw721_deposits_by_owner_page(app, deposit_contract, owner, None, None)
+    }
+
+    fn get_cw721_deposits_by_owner_page(app: &App, deposit_contract: &DepositContract, owner:String, start_after: Option<(String, String)>, limit: Option<u32>) -> Cw721DepositsByOwnerResponse {
+        app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Cw721DepositsByOwner { address: owner, start_after, limit })
+            .unwrap()
+    }
+
+    fn get_cw721_deposit_changelog(app: &App, deposit_contract: &DepositContract, nft_contract:&NftContract, token_id:String) -> Cw721DepositChangelogResponse {
+        app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Cw721DepositChangelog { contract_addr: nft_contract.addr().to_string(), token_id, start_after: None, limit: None })
+            .unwrap()
+    }
+
+    fn mint_and_deposit_nft(app: &mut App, deposit_contract: &DepositContract, cw721_contract: &NftContract, token_id:String) {
+        let mint_msg = nft::contract::MintMsg{token_id:token_id.clone(), owner:USER.to_string(), token_uri:Some("url".to_string()), extension:None };
+        let cosmos_msg = cw721_contract.call(nft::contract::ExecuteMsg::Mint(mint_msg)).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+
+        let hook_msg = Cw721HookMsg::Deposit { };
+        let msg = nft::contract::ExecuteMsg::SendNft { contract: deposit_contract.addr().to_string(), token_id, msg: to_binary(&hook_msg).unwrap() };
+        let cosmos_msg = cw721_contract.call(msg).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+    }
+
+    fn get_lock_schedule(app: &App, deposit_contract: &DepositContract) -> LockScheduleResponse {
+        app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::LockSchedule { start_after: None, limit: None })
+            .unwrap()
+    }
+
+    fn cw20_deposit(app: &mut App, deposit_contract: &DepositContract, cw20_contract: &Cw20Contract, amount:u64) {
+        cw20_deposit_as(app, USER, deposit_contract, cw20_contract, amount);
+    }
+
+    fn cw20_deposit_as(app: &mut App, sender: &str, deposit_contract: &DepositContract, cw20_contract: &Cw20Contract, amount:u64) {
+        let hook_msg = Cw20HookMsg::Deposit { };
+        let msg = Cw20ExecuteMsg::Send { contract: deposit_contract.addr().to_string(), amount: Uint128::from(amount), msg: to_binary(&hook_msg).unwrap() };
+        let cosmos_msg = cw20_contract.call(msg).unwrap();
+        app.execute(Addr::unchecked(sender), cosmos_msg).unwrap();
+    }
+
+    fn get_pending_rewards(app: &App, deposit_contract: &DepositContract, address: &str, staked_token: &Cw20Contract) -> Vec<(RewardToken, Uint128)> {
+        let res: PendingRewardsResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::PendingRewards { address: address.to_string(), staked_token: staked_token.addr().to_string() })
+            .unwrap();
+        res.rewards
+    }
+
+    fn set_reward_pool(app: &mut App, deposit_contract: &DepositContract, staked_token: &Cw20Contract, reward: RewardToken, reward_per_block: u128) {
+        let msg = ExecuteMsg::SetRewardPool { staked_token: staked_token.addr().to_string(), reward, reward_per_block: Uint128::new(reward_per_block) };
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+    }
+
+    fn get_cw20_lots(app: &App, deposit_contract: &DepositContract, cw20_contract: &Cw20Contract) -> Cw20LotsResponse {
+        app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Cw20Lots { address: USER.to_string(), contract: cw20_contract.addr().to_string(), start_after: None, limit: None })
+            .unwrap()
+    }
+
+    fn get_config(app: &App, deposit_contract: &DepositContract) -> ConfigResponse {
+        app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Config {})
             .unwrap()
     }
 
-    fn get_cw721_deposits_by_owner(app: &App, deposit_contract: &DepositContract, owner:String) -> Cw721DepositResponse {
+    fn get_allowed_tokens(app: &App, deposit_contract: &DepositContract, start_after: Option<String>, limit: Option<u32>) -> AllowedTokensResponse {
         app.wrap()
-            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Cw721DepositsByOwner { address: owner })
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::AllowedTokens { start_after, limit })
             .unwrap()
     }
 
@@ -160,19 +390,60 @@ mod tests {
         let (mut app, deposit_id, _cw20_id, _cw721_id) = store_code();
         let deposit_contract = deposit_instantiate(&mut app, deposit_id);
 
-        let balance = get_balance(&app, USER.to_string(), "denom".to_string());
-        println!("Intial Balance {:?}", balance);
-
         let msg = ExecuteMsg::Deposit { };
 
         let cosmos_msg = deposit_contract.call(msg, vec![coin(1000, "denom")]).unwrap();
         app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
 
-        let balance = get_balance(&app, deposit_contract.addr().into_string(), deposit_contract.addr().into_string());
-        println!("Deposit Contract {:?}", balance);
+        assert_eq!(get_balance(&app, deposit_contract.addr().into_string(), "denom".to_string()), coin(1000, "denom"));
+        assert_eq!(get_balance(&app, USER.to_string(), "denom".to_string()).amount, Uint128::zero());
 
-        let balance = get_balance(&app, USER.to_string(), "denom".to_string());
-        println!("Post {:?}", balance);
+        let deposits = get_deposits(&app, &deposit_contract);
+        assert_eq!(deposits.deposits[0].1.coins, coin(1000, "denom"));
+    }
+
+    #[test]
+    fn deposit_multiple_native_coins() {
+        let (mut app, deposit_id, _cw20_id, _cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+
+        let msg = ExecuteMsg::Deposit { };
+        let cosmos_msg = deposit_contract.call(msg, vec![coin(300, NATIVE_DENOM), coin(200, OTHER_DENOM)]).unwrap();
+        let res = app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+        assert_eq!(res.events.iter().filter(|e| e.ty == "wasm-deposit").count(), 2);
+
+        let msg = ExecuteMsg::Deposit { };
+        let cosmos_msg = deposit_contract.call(msg, vec![coin(100, OTHER_DENOM)]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+
+        let deposits = get_deposits(&app, &deposit_contract);
+        assert_eq!(deposits.deposits.len(), 2);
+        assert_eq!(deposits.deposits[0].1.coins, coin(300, NATIVE_DENOM));
+        assert_eq!(deposits.deposits[0].1.count, 1);
+        assert_eq!(deposits.deposits[1].1.coins, coin(300, OTHER_DENOM));
+        assert_eq!(deposits.deposits[1].1.count, 2);
+
+        let balance = get_balance(&app, deposit_contract.addr().into_string(), OTHER_DENOM.to_string());
+        assert_eq!(balance.amount, Uint128::new(300));
+    }
+
+    #[test]
+    fn deposit_native_rejects_invalid_coins() {
+        let (mut app, deposit_id, _cw20_id, _cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+
+        //no funds at all
+        let cosmos_msg = deposit_contract.call(ExecuteMsg::Deposit { }, vec![]).unwrap();
+        let err = app.execute(Addr::unchecked(USER), cosmos_msg).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::InvalidCoin {});
+
+        //the same denom twice
+        let cosmos_msg = deposit_contract.call(ExecuteMsg::Deposit { }, vec![coin(100, NATIVE_DENOM), coin(100, NATIVE_DENOM)]).unwrap();
+        let err = app.execute(Addr::unchecked(USER), cosmos_msg).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::InvalidCoin {});
+
+        let deposits = get_deposits(&app, &deposit_contract);
+        assert!(deposits.deposits.is_empty());
     }
 
     #[test]
@@ -217,7 +488,7 @@ mod tests {
         block.height = app.block_info().height.checked_add(20).unwrap();
         app.set_block(block);
 
-        let msg = ExecuteMsg::WithdrawCw20 {address:cw20_contract.addr().to_string(), amount:Uint128::from(500u64)};
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Cw20 { contract: cw20_contract.addr().to_string() }, amount: Uint128::new(500), recipient: None, msg: None};
 
         let execute_msg = WasmMsg::Execute { contract_addr: deposit_contract.addr().to_string(), msg: to_binary(&msg).unwrap(), funds: vec![] };
         app.execute(Addr::unchecked(USER), execute_msg.into()).unwrap();
@@ -225,46 +496,1879 @@ mod tests {
     }
 
     #[test]
-    fn mint_then_deposit_nft_then_withdraw_nft_back_to_owner() {
-        let (mut app, deposit_id, _cw20_id, cw721_id) = store_code();
+    fn withdraw_native_errors() {
+        let (mut app, deposit_id, _cw20_id, _cw721_id) = store_code();
         let deposit_contract = deposit_instantiate(&mut app, deposit_id);
-        let cw721_contract = cw721_instantiate(&mut app, cw721_id, "NFT".to_string(), "NFT".to_string(), USER.to_string());
 
-        //mint NFT to User
-        let mint_msg = nft::contract::MintMsg{token_id:"0".to_string(), owner:USER.to_string(), token_uri:Some("url".to_string()), extension:None };
-        let msg = nft::contract::ExecuteMsg::Mint(mint_msg);
-        let cosmos_msg = cw721_contract.call(msg).unwrap();
+        let cosmos_msg = deposit_contract.call(ExecuteMsg::Deposit { }, vec![coin(100, NATIVE_DENOM)]).unwrap();
         app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
 
-        //check to see if User is the owner.
-        let owner = get_owner_of(&app, &cw721_contract, "0".to_string());
-        assert_eq!(owner.owner, USER.to_string());
-        println!("{:?}", owner);
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Native { denom: OTHER_DENOM.to_string() }, amount: Uint128::new(50), recipient: None, msg: None };
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        let err = app.execute(Addr::unchecked(USER), cosmos_msg).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::UnknownDenom { denom: OTHER_DENOM.to_string() });
 
-        //deposit NFT to Deposit Contract
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Native { denom: NATIVE_DENOM.to_string() }, amount: Uint128::new(150), recipient: None, msg: None };
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        let err = app.execute(Addr::unchecked(USER), cosmos_msg).unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InsufficientBalance { asset: NATIVE_DENOM.to_string(), available: Uint128::new(100), requested: Uint128::new(150) }
+        );
+
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Native { denom: NATIVE_DENOM.to_string() }, amount: Uint128::zero(), recipient: None, msg: None };
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        let err = app.execute(Addr::unchecked(USER), cosmos_msg).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::ZeroAmount {});
+
+        //partial withdrawals keep working past the number of deposits
+        for _ in 0..2 {
+            let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Native { denom: NATIVE_DENOM.to_string() }, amount: Uint128::new(50), recipient: None, msg: None };
+            let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+            app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+        }
+        let balance = get_balance(&app, USER.to_string(), NATIVE_DENOM.to_string());
+        assert_eq!(balance.amount, Uint128::new(1000));
+    }
+
+    #[test]
+    fn withdraw_cw20_errors() {
+        let (mut app, deposit_id, cw20_id, _cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Cw20 { contract: cw20_contract.addr().to_string() }, amount: Uint128::new(500), recipient: None, msg: None};
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        let err = app.execute(Addr::unchecked(USER), cosmos_msg).unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::UnknownCw20Position { owner: USER.to_string(), contract: cw20_contract.addr().to_string() }
+        );
+
+        let hook_msg = Cw20HookMsg::Deposit { };
+        let msg = Cw20ExecuteMsg::Send { contract: deposit_contract.addr().to_string(), amount: Uint128::from(500u64), msg: to_binary(&hook_msg).unwrap() };
+        let cosmos_msg = cw20_contract.call(msg).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Cw20 { contract: cw20_contract.addr().to_string() }, amount: Uint128::new(500), recipient: None, msg: None};
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        let err = app.execute(Addr::unchecked(USER), cosmos_msg).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::StakeDurationNotPassed {});
+
+        app.update_block(|block| block.height += 20);
+
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Cw20 { contract: cw20_contract.addr().to_string() }, amount: Uint128::new(600), recipient: None, msg: None};
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        let err = app.execute(Addr::unchecked(USER), cosmos_msg).unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InsufficientBalance { asset: cw20_contract.addr().to_string(), available: Uint128::new(500), requested: Uint128::new(600) }
+        );
+    }
+
+    #[test]
+    fn cw20_lock_duration_is_configurable_per_contract() {
+        let (mut app, deposit_id, cw20_id, _cw721_id) = store_code();
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        let other_cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        let msg = InstantiateMsg {
+            admin: None,
+            default_lock_duration: Some(Duration::Height(5)),
+            cw20_lock_durations: vec![Cw20LockDuration { contract: cw20_contract.addr().to_string(), duration: Duration::Time(100) }],
+            open: true,
+            allowed_tokens: vec![],
+        };
+        let deposit_contract = deposit_instantiate_with_msg(&mut app, deposit_id, msg);
+
+        let schedule = get_lock_schedule(&app, &deposit_contract);
+        assert_eq!(schedule.default_lock_duration, Duration::Height(5));
+        assert_eq!(schedule.cw20_lock_durations.len(), 1);
+
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 100);
+        let block = app.block_info();
+        let deposits = get_cw20_deposits(&app, &deposit_contract);
+        assert_eq!(deposits.deposits[0].1.stake_time, Expiration::AtTime(block.time.plus_seconds(100)));
+
+        //the other token falls back to the default
+        cw20_deposit(&mut app, &deposit_contract, &other_cw20_contract, 100);
+        let deposits = get_cw20_deposits(&app, &deposit_contract);
+        let other = deposits.deposits.iter().find(|(k, _)| k == &other_cw20_contract.addr().to_string()).unwrap();
+        assert_eq!(other.1.stake_time, Expiration::AtHeight(block.height + 5));
+
+        //only the admin can change the schedule
+        let msg = ExecuteMsg::UpdateCw20LockDuration { contract: other_cw20_contract.addr().to_string(), duration: Some(Duration::Height(1)) };
+        let cosmos_msg = deposit_contract.call(msg.clone(), vec![]).unwrap();
+        let err = app.execute(Addr::unchecked(USER), cosmos_msg).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::Unauthorized {});
+
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+
+        let msg = ExecuteMsg::UpdateCw20LockDuration { contract: cw20_contract.addr().to_string(), duration: None };
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+
+        let msg = ExecuteMsg::UpdateConfig { default_lock_duration: Some(Duration::Height(50)), open: None };
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+
+        let schedule = get_lock_schedule(&app, &deposit_contract);
+        assert_eq!(schedule.default_lock_duration, Duration::Height(50));
+        assert_eq!(
+            schedule.cw20_lock_durations,
+            vec![Cw20LockDuration { contract: other_cw20_contract.addr().to_string(), duration: Duration::Height(1) }]
+        );
+    }
+
+    #[test]
+    fn cw20_lots_unlock_independently() {
+        let (mut app, deposit_id, cw20_id, _cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 300);
+        app.update_block(|block| block.height += 10);
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 200);
+        app.update_block(|block| block.height += 10);
+
+        //the first lot has matured, the second has not been re-locked with it
+        let lots = get_cw20_lots(&app, &deposit_contract, &cw20_contract);
+        assert_eq!(lots.lots.len(), 2);
+        assert_eq!(lots.unlocked, Uint128::new(300));
+        assert_eq!(lots.locked, Uint128::new(200));
+        assert_eq!(lots.total, Uint128::new(500));
+        let page: Cw20LotsResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Cw20Lots { address: USER.to_string(), contract: cw20_contract.addr().to_string(), start_after: Some(1), limit: Some(1) })
+            .unwrap();
+        assert_eq!((page.total, page.locked, page.unlocked), (Uint128::new(500), Uint128::new(200), Uint128::zero()));
+
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Cw20 { contract: cw20_contract.addr().to_string() }, amount: Uint128::new(400), recipient: None, msg: None};
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        let err = app.execute(Addr::unchecked(USER), cosmos_msg).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::StakeDurationNotPassed {});
+
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Cw20 { contract: cw20_contract.addr().to_string() }, amount: Uint128::new(250), recipient: None, msg: None};
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+
+        let lots = get_cw20_lots(&app, &deposit_contract, &cw20_contract);
+        assert_eq!(lots.lots.len(), 2);
+        assert_eq!(lots.lots[0].1.amount, Uint128::new(50));
+        assert_eq!(lots.unlocked, Uint128::new(50));
+
+        app.update_block(|block| block.height += 10);
+
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Cw20 { contract: cw20_contract.addr().to_string() }, amount: Uint128::new(250), recipient: None, msg: None};
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+
+        let lots = get_cw20_lots(&app, &deposit_contract, &cw20_contract);
+        assert!(lots.lots.is_empty());
+        let balance = get_cw20_balance(&app, &cw20_contract, USER.to_string());
+        assert_eq!(balance.balance, Uint128::new(10000));
+    }
+
+    #[test]
+    fn admin_transfer_is_two_step() {
+        let (mut app, deposit_id, _cw20_id, _cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+
+        let config = get_config(&app, &deposit_contract);
+        assert_eq!(config.admin, ADMIN.to_string());
+        assert_eq!(config.pending_admin, None);
+
+        let cosmos_msg = deposit_contract.call(ExecuteMsg::AcceptAdmin {}, vec![]).unwrap();
+        let err = app.execute(Addr::unchecked(USER), cosmos_msg).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::NoPendingAdmin {});
+
+        let msg = ExecuteMsg::ProposeNewAdmin { new_admin: USER.to_string() };
+        let cosmos_msg = deposit_contract.call(msg.clone(), vec![]).unwrap();
+        let err = app.execute(Addr::unchecked(USER), cosmos_msg).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::Unauthorized {});
+
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+
+        //proposing does not hand over control yet
+        let config = get_config(&app, &deposit_contract);
+        assert_eq!(config.admin, ADMIN.to_string());
+        assert_eq!(config.pending_admin, Some(USER.to_string()));
+
+        let cosmos_msg = deposit_contract.call(ExecuteMsg::AcceptAdmin {}, vec![]).unwrap();
+        let err = app.execute(Addr::unchecked(OTHER_USER), cosmos_msg).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::Unauthorized {});
+
+        let cosmos_msg = deposit_contract.call(ExecuteMsg::AcceptAdmin {}, vec![]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+
+        let config = get_config(&app, &deposit_contract);
+        assert_eq!(config.admin, USER.to_string());
+        assert_eq!(config.pending_admin, None);
+
+        //the old admin has lost access
+        let msg = ExecuteMsg::UpdateConfig { default_lock_duration: Some(Duration::Height(1)), open: None };
+        let cosmos_msg = deposit_contract.call(msg.clone(), vec![]).unwrap();
+        let err = app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::Unauthorized {});
+
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+        assert_eq!(get_config(&app, &deposit_contract).default_lock_duration, Duration::Height(1));
+    }
+
+    #[test]
+    fn empty_instantiate_msg_keeps_the_old_defaults() {
+        let (mut app, deposit_id, cw20_id, _cw721_id) = store_code();
+        let address = app.instantiate_contract(deposit_id, Addr::unchecked(ADMIN), &Empty {}, &[], "deposit-cw20", None).unwrap();
+        let deposit_contract = DepositContract(address);
+        let config = get_config(&app, &deposit_contract);
+        assert_eq!(config.admin, ADMIN.to_string());
+        assert_eq!(config.default_lock_duration, Duration::Height(20));
+        assert!(config.open);
+
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 100);
+        assert_eq!(get_cw20_lots(&app, &deposit_contract, &cw20_contract).locked, Uint128::new(100));
+    }
+
+    #[test]
+    fn only_allowed_tokens_can_be_deposited() {
+        let (mut app, deposit_id, cw20_id, cw721_id) = store_code();
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        let other_cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        let cw721_contract = cw721_instantiate(&mut app, cw721_id, "NFT".to_string(), "NFT".to_string(), USER.to_string());
+        let msg = InstantiateMsg {
+            admin: None,
+            default_lock_duration: None,
+            cw20_lock_durations: vec![],
+            open: false,
+            allowed_tokens: vec![AllowedToken { contract: cw20_contract.addr().to_string(), kind: TokenKind::Cw20 }],
+        };
+        let deposit_contract = deposit_instantiate_with_msg(&mut app, deposit_id, msg);
+
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 100);
+
+        let hook_msg = Cw20HookMsg::Deposit { };
+        let msg = Cw20ExecuteMsg::Send { contract: deposit_contract.addr().to_string(), amount: Uint128::from(100u64), msg: to_binary(&hook_msg).unwrap() };
+        let cosmos_msg = other_cw20_contract.call(msg).unwrap();
+        let err = app.execute(Addr::unchecked(USER), cosmos_msg).unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::TokenNotAllowed { contract: other_cw20_contract.addr().to_string() }
+        );
+
+        let mint_msg = nft::contract::MintMsg{token_id:"0".to_string(), owner:USER.to_string(), token_uri:None, extension:None };
+        let cosmos_msg = cw721_contract.call(nft::contract::ExecuteMsg::Mint(mint_msg)).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
         let hook_msg = Cw721HookMsg::Deposit { };
-        let msg = nft::contract::ExecuteMsg::SendNft { contract: deposit_contract.addr().to_string(), token_id: "0".to_string(), msg: to_binary(&hook_msg).unwrap() };
-        let cosmos_msg = cw721_contract.call(msg).unwrap();
+        let send_nft = nft::contract::ExecuteMsg::SendNft { contract: deposit_contract.addr().to_string(), token_id: "0".to_string(), msg: to_binary(&hook_msg).unwrap() };
+        let cosmos_msg = cw721_contract.call(send_nft.clone()).unwrap();
+        let err = app.execute(Addr::unchecked(USER), cosmos_msg).unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::TokenNotAllowed { contract: cw721_contract.addr().to_string() }
+        );
+
+        let msg = ExecuteMsg::AddAllowedToken { contract: cw721_contract.addr().to_string(), kind: TokenKind::Cw721 };
+        let cosmos_msg = deposit_contract.call(msg.clone(), vec![]).unwrap();
+        let err = app.execute(Addr::unchecked(USER), cosmos_msg).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::Unauthorized {});
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+
+        let cosmos_msg = cw721_contract.call(send_nft).unwrap();
         app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
 
-        //check to see if Deposit Contract is the owner.
-        let owner = get_owner_of(&app, &cw721_contract, "0".to_string());
-        assert_eq!(owner.owner, deposit_contract.addr().to_string());
-        println!("{:?}", owner);
+        let tokens = get_allowed_tokens(&app, &deposit_contract, None, None);
+        assert_eq!(tokens.tokens.len(), 2);
+        let page = get_allowed_tokens(&app, &deposit_contract, None, Some(1));
+        assert_eq!(page.tokens.len(), 1);
+        let page = get_allowed_tokens(&app, &deposit_contract, Some(page.tokens[0].contract.clone()), Some(1));
+        assert_eq!(page.tokens, vec![tokens.tokens[1].clone()]);
 
+        let msg = ExecuteMsg::RemoveAllowedToken { contract: cw20_contract.addr().to_string() };
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+        let tokens = get_allowed_tokens(&app, &deposit_contract, None, None);
+        assert_eq!(tokens.tokens.len(), 1);
 
-        //withdraw NFT from Deposit Contract
-        let msg = ExecuteMsg::WithdrawNft {contract_addr:cw721_contract.addr().to_string(), token_id:"0".to_string()};
-        let execute_msg = WasmMsg::Execute { contract_addr: deposit_contract.addr().to_string(), msg: to_binary(&msg).unwrap(), funds: vec![] };
+        //open mode accepts anything again
+        let msg = ExecuteMsg::UpdateConfig { default_lock_duration: None, open: Some(true) };
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 100);
+    }
+
+    fn legacy_instantiate(app: &mut App, legacy_id: u64, contract: &str, version: &str) -> DepositContract {
+        let msg = legacy::LegacyInstantiateMsg { contract: contract.to_string(), version: version.to_string() };
+        let addr = app
+            .instantiate_contract(legacy_id, Addr::unchecked(ADMIN), &msg, &[], "deposit-cw20", Some(ADMIN.to_string()))
+            .unwrap();
+        DepositContract(addr)
+    }
+
+    fn migrate_msg() -> MigrateMsg {
+        MigrateMsg { admin: Some("admin".to_string()), default_lock_duration: None, open: None }
+    }
+
+    #[test]
+    fn migrate_populated_v0_1_0_instance() {
+        let (mut app, _deposit_id, cw20_id, _cw721_id) = store_code();
+        let legacy_id = app.store_code(contract_legacy_deposit());
+        let deposit_id = app.store_code(contract_deposit_cw20());
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        let deposit_contract = legacy_instantiate(&mut app, legacy_id, "deposit-cw20-example", "0.1.0");
+
+        let msg = legacy::LegacyExecuteMsg::Deposit {};
+        let execute_msg = WasmMsg::Execute { contract_addr: deposit_contract.addr().to_string(), msg: to_binary(&msg).unwrap(), funds: vec![coin(400, NATIVE_DENOM)] };
+        app.execute(Addr::unchecked(USER), execute_msg.into()).unwrap();
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 200);
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 300);
+        //deposited once and withdrawn twice leaves a count of -1
+        let msg = legacy::LegacyExecuteMsg::Deposit {};
+        let execute_msg = WasmMsg::Execute { contract_addr: deposit_contract.addr().to_string(), msg: to_binary(&msg).unwrap(), funds: vec![coin(100, OTHER_DENOM)] };
         app.execute(Addr::unchecked(USER), execute_msg.into()).unwrap();
+        for _ in 0..2 {
+            let msg = legacy::LegacyExecuteMsg::Withdraw { amount: 25, denom: OTHER_DENOM.to_string() };
+            let execute_msg = WasmMsg::Execute { contract_addr: deposit_contract.addr().to_string(), msg: to_binary(&msg).unwrap(), funds: vec![] };
+            app.execute(Addr::unchecked(USER), execute_msg.into()).unwrap();
+        }
 
-        //check to see if User is the owner.
-        let owner = get_owner_of(&app, &cw721_contract, "0".to_string());
-        assert_eq!(owner.owner, USER.to_string());
-        println!("{:?}", owner);
+        //v0.1.0 stored no admin, so it has to be supplied
+        let no_admin = MigrateMsg { admin: None, default_lock_duration: None, open: None };
+        let err = app.migrate_contract(Addr::unchecked(ADMIN), deposit_contract.addr(), &no_admin, deposit_id).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::MigrationOptionRequired { option: "admin".to_string() });
+
+        app.migrate_contract(Addr::unchecked(ADMIN), deposit_contract.addr(), &migrate_msg(), deposit_id).unwrap();
+
+        let version = cw2::query_contract_info(&app.wrap(), deposit_contract.addr()).unwrap();
+        assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+        let deposits = get_deposits(&app, &deposit_contract);
+        assert_eq!(deposits.deposits[0].1.count, 1u64);
+        assert_eq!(deposits.deposits[0].1.coins, coin(400, NATIVE_DENOM));
+        assert_eq!(deposits.deposits[1].1.count, 0u64);
+        assert_eq!(deposits.deposits[1].1.coins, coin(50, OTHER_DENOM));
+
+        //totals are rebuilt from the carried over positions
+        assert_eq!(get_totals(&app, &deposit_contract, AssetKind::Native)[0].1, AssetTotal { amount: Uint128::new(400), depositors: 1 });
+        assert_eq!(get_totals(&app, &deposit_contract, AssetKind::Cw20)[0].1, AssetTotal { amount: Uint128::new(500), depositors: 1 });
+
+        let config = get_config(&app, &deposit_contract);
+        assert_eq!(config.admin, "admin".to_string());
+        assert_eq!(config.default_lock_duration, Duration::Height(20));
+        assert!(config.open);
+
+        let lots = get_cw20_lots(&app, &deposit_contract, &cw20_contract);
+        assert_eq!(lots.lots.len(), 1);
+        assert_eq!(lots.locked, Uint128::new(500));
+
+        //migrated positions keep working with the new code
+        app.update_block(|block| block.height += 20);
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Cw20 { contract: cw20_contract.addr().to_string() }, amount: Uint128::new(500), recipient: None, msg: None};
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Native { denom: NATIVE_DENOM.to_string() }, amount: Uint128::new(400), recipient: None, msg: None };
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+        assert_eq!(get_deposits(&app, &deposit_contract).deposits[0].1.count, 0u64);
+    }
+
+    #[test]
+    fn migrate_v0_2_0_keeps_deposit_history_queryable() {
+        let (mut app, _deposit_id, _cw20_id, _cw721_id) = store_code();
+        let legacy_id = app.store_code(contract_legacy_deposit());
+        let deposit_id = app.store_code(contract_deposit_cw20());
+        let deposit_contract = legacy_instantiate(&mut app, legacy_id, "deposit-cw20-example", "0.2.0");
+
+        let msg = legacy::LegacyExecuteMsg::Deposit {};
+        let execute_msg = WasmMsg::Execute { contract_addr: deposit_contract.addr().to_string(), msg: to_binary(&msg).unwrap(), funds: vec![coin(400, NATIVE_DENOM)] };
+        app.execute(Addr::unchecked(USER), execute_msg.into()).unwrap();
+        app.update_block(|block| block.height += 1);
+
+        let upgrade = app.block_info().height;
+        app.migrate_contract(Addr::unchecked(ADMIN), deposit_contract.addr(), &migrate_msg(), deposit_id).unwrap();
+        app.update_block(|block| block.height += 1);
+
+        let msg = ExecuteMsg::Deposit {};
+        let cosmos_msg = deposit_contract.call(msg, vec![coin(100, NATIVE_DENOM)]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+        app.update_block(|block| block.height += 1);
+
+        let deposits_at = |height: u64| -> DepositResponse {
+            app.wrap()
+                .query_wasm_smart(deposit_contract.addr(), &QueryMsg::DepositsAt { address: USER.to_string(), height, start_after: None, limit: None })
+                .unwrap()
+        };
+        assert_eq!(deposits_at(upgrade).deposits[0].1.coins, coin(400, NATIVE_DENOM));
+        assert_eq!(deposits_at(upgrade + 1).deposits[0].1.coins, coin(400, NATIVE_DENOM));
+        assert_eq!(deposits_at(upgrade + 2).deposits[0].1.coins, coin(500, NATIVE_DENOM));
+    }
+
+    #[test]
+    fn migrate_v0_5_0_indexes_past_nft_custody() {
+        let (mut app, _deposit_id, _cw20_id, _cw721_id) = store_code();
+        let legacy_id = app.store_code(contract_legacy_deposit());
+        let deposit_id = app.store_code(contract_deposit_cw20());
+        let deposit_contract = legacy_instantiate(&mut app, legacy_id, "deposit-cw20-example", "0.5.0");
+
+        let custody = |owner: Option<&str>, token_id: &str| {
+            let msg = legacy::LegacyExecuteMsg::Cw721Custody { owner: owner.map(str::to_string), contract: "collection".to_string(), token_id: token_id.to_string() };
+            WasmMsg::Execute { contract_addr: deposit_contract.addr().to_string(), msg: to_binary(&msg).unwrap(), funds: vec![] }
+        };
+        let start = app.block_info().height;
+        app.execute(Addr::unchecked(USER), custody(Some(USER), "0").into()).unwrap();
+        app.execute(Addr::unchecked(USER), custody(Some(USER), "1").into()).unwrap();
+        app.update_block(|block| block.height += 1);
+        app.execute(Addr::unchecked(USER), custody(Some(OTHER_USER), "0").into()).unwrap();
+        app.execute(Addr::unchecked(USER), custody(None, "1").into()).unwrap();
+        app.update_block(|block| block.height += 1);
+
+        app.migrate_contract(Addr::unchecked(ADMIN), deposit_contract.addr(), &migrate_msg(), deposit_id).unwrap();
+
+        let owner_at = |address: &str, height: u64| -> Vec<String> {
+            let res: Cw721DepositsByOwnerResponse = app.wrap()
+                .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Cw721DepositsByOwnerAt { address: address.to_string(), height, start_after: None, limit: None })
+                .unwrap();
+            res.deposits.into_iter().map(|(_, nft)| nft.token_id).collect()
+        };
+        assert_eq!(owner_at(USER, start + 1), vec!["0".to_string(), "1".to_string()]);
+        assert_eq!(owner_at(USER, start + 2), Vec::<String>::new());
+        assert_eq!(owner_at(OTHER_USER, start + 1), Vec::<String>::new());
+        assert_eq!(owner_at(OTHER_USER, start + 2), vec!["0".to_string()]);
+    }
+
+    fn get_totals(app: &App, deposit_contract: &DepositContract, kind: AssetKind) -> Vec<(String, AssetTotal)> {
+        let res: TotalsResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Totals { kind, start_after: None, limit: None })
+            .unwrap();
+        res.totals
     }
 
+    #[test]
+    fn totals_follow_every_deposit_and_withdraw() {
+        let (mut app, deposit_id, cw20_id, cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        let cw721_contract = cw721_instantiate(&mut app, cw721_id, "NFT".to_string(), "NFT".to_string(), USER.to_string());
+        app.send_tokens(Addr::unchecked(USER), Addr::unchecked(OTHER_USER), &[coin(100, NATIVE_DENOM)]).unwrap();
 
+        let msg = ExecuteMsg::Deposit {};
+        let cosmos_msg = deposit_contract.call(msg, vec![coin(300, NATIVE_DENOM), coin(50, OTHER_DENOM)]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+        let msg = ExecuteMsg::Deposit {};
+        let cosmos_msg = deposit_contract.call(msg, vec![coin(100, NATIVE_DENOM)]).unwrap();
+        app.execute(Addr::unchecked(OTHER_USER), cosmos_msg).unwrap();
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 100);
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 200);
+        mint_and_deposit_nft(&mut app, &deposit_contract, &cw721_contract, "0".to_string());
+        mint_and_deposit_nft(&mut app, &deposit_contract, &cw721_contract, "1".to_string());
 
+        assert_eq!(get_totals(&app, &deposit_contract, AssetKind::Native), vec![
+            (NATIVE_DENOM.to_string(), AssetTotal { amount: Uint128::new(400), depositors: 2 }),
+            (OTHER_DENOM.to_string(), AssetTotal { amount: Uint128::new(50), depositors: 1 }),
+        ]);
+        assert_eq!(get_totals(&app, &deposit_contract, AssetKind::Cw20), vec![(cw20_contract.addr().to_string(), AssetTotal { amount: Uint128::new(300), depositors: 1 })]);
+        assert_eq!(get_totals(&app, &deposit_contract, AssetKind::Cw721), vec![(cw721_contract.addr().to_string(), AssetTotal { amount: Uint128::new(2), depositors: 1 })]);
 
+        let page: TotalsResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Totals { kind: AssetKind::Native, start_after: None, limit: Some(1) })
+            .unwrap();
+        assert_eq!(page.totals[0].0, NATIVE_DENOM.to_string());
+        let page: TotalsResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Totals { kind: AssetKind::Native, start_after: page.next_start_after, limit: Some(1) })
+            .unwrap();
+        assert_eq!(page.totals[0].0, OTHER_DENOM.to_string());
+
+        //partial withdrawals keep the depositor, emptying a position drops it
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Native { denom: NATIVE_DENOM.to_string() }, amount: Uint128::new(100), recipient: None, msg: None };
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(OTHER_USER), cosmos_msg).unwrap();
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Native { denom: NATIVE_DENOM.to_string() }, amount: Uint128::new(100), recipient: None, msg: None };
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+
+        app.update_block(|block| block.height += 20);
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Cw20 { contract: cw20_contract.addr().to_string() }, amount: Uint128::new(300), recipient: None, msg: None};
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Cw721 { contract: cw721_contract.addr().to_string(), token_id: "0".to_string() }, amount: Uint128::one(), recipient: None, msg: None};
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+
+        assert_eq!(get_totals(&app, &deposit_contract, AssetKind::Native)[0].1, AssetTotal { amount: Uint128::new(200), depositors: 1 });
+        assert_eq!(get_totals(&app, &deposit_contract, AssetKind::Cw20)[0].1, AssetTotal { amount: Uint128::zero(), depositors: 0 });
+        assert_eq!(get_totals(&app, &deposit_contract, AssetKind::Cw721)[0].1, AssetTotal { amount: Uint128::new(1), depositors: 1 });
+
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Cw721 { contract: cw721_contract.addr().to_string(), token_id: "1".to_string() }, amount: Uint128::one(), recipient: None, msg: None};
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+        assert_eq!(get_totals(&app, &deposit_contract, AssetKind::Cw721)[0].1, AssetTotal { amount: Uint128::zero(), depositors: 0 });
+    }
+
+    #[test]
+    fn rewards_are_split_between_stakers_by_stake() {
+        let (mut app, deposit_id, cw20_id, _cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        let msg = Cw20ExecuteMsg::Transfer { recipient: OTHER_USER.to_string(), amount: Uint128::new(1000) };
+        app.execute(Addr::unchecked(USER), cw20_contract.call(msg).unwrap()).unwrap();
+
+        //only the admin sets up pools
+        let reward = RewardToken::Native { denom: NATIVE_DENOM.to_string() };
+        let msg = ExecuteMsg::SetRewardPool { staked_token: cw20_contract.addr().to_string(), reward: reward.clone(), reward_per_block: Uint128::new(10) };
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        let err = app.execute(Addr::unchecked(USER), cosmos_msg).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::Unauthorized {});
+        set_reward_pool(&mut app, &deposit_contract, &cw20_contract, reward.clone(), 10);
+
+        //anyone can fund a pool
+        let msg = ExecuteMsg::FundRewards { staked_token: cw20_contract.addr().to_string() };
+        let cosmos_msg = deposit_contract.call(msg, vec![coin(1000, NATIVE_DENOM)]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 100);
+        app.update_block(|block| block.height += 10);
+        assert_eq!(get_pending_rewards(&app, &deposit_contract, USER, &cw20_contract), vec![(reward.clone(), Uint128::new(100))]);
+
+        //a second staker with three times the stake earns three quarters from now on
+        cw20_deposit_as(&mut app, OTHER_USER, &deposit_contract, &cw20_contract, 300);
+        app.update_block(|block| block.height += 10);
+        assert_eq!(get_pending_rewards(&app, &deposit_contract, USER, &cw20_contract), vec![(reward.clone(), Uint128::new(125))]);
+        assert_eq!(get_pending_rewards(&app, &deposit_contract, OTHER_USER, &cw20_contract), vec![(reward.clone(), Uint128::new(75))]);
+
+        let before = get_balance(&app, USER.to_string(), NATIVE_DENOM.to_string());
+        let msg = ExecuteMsg::ClaimRewards { staked_token: cw20_contract.addr().to_string() };
+        let cosmos_msg = deposit_contract.call(msg.clone(), vec![]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+        let after = get_balance(&app, USER.to_string(), NATIVE_DENOM.to_string());
+        assert_eq!(after.amount - before.amount, Uint128::new(125));
+        assert_eq!(get_pending_rewards(&app, &deposit_contract, USER, &cw20_contract), vec![(reward.clone(), Uint128::zero())]);
+
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        let err = app.execute(Addr::unchecked(USER), cosmos_msg).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::NoRewardsToClaim {});
+
+        let pools: RewardPoolsResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::RewardPools { staked_token: cw20_contract.addr().to_string(), start_after: None, limit: None })
+            .unwrap();
+        assert_eq!(pools.pools[0].0, NATIVE_DENOM.to_string());
+        assert_eq!(pools.pools[0].1.balance, Uint128::new(800));
+    }
+
+    #[test]
+    fn cw20_reward_pool_emits_no_more_than_funded() {
+        let (mut app, deposit_id, cw20_id, _cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        let reward_contract = cw_20_instantiate(&mut app, cw20_id);
+
+        let reward = RewardToken::Cw20 { contract: reward_contract.addr().to_string() };
+        set_reward_pool(&mut app, &deposit_contract, &cw20_contract, reward.clone(), 10);
+
+        //there is no native pool to fund
+        let msg = ExecuteMsg::FundRewards { staked_token: cw20_contract.addr().to_string() };
+        let cosmos_msg = deposit_contract.call(msg, vec![coin(100, NATIVE_DENOM)]).unwrap();
+        let err = app.execute(Addr::unchecked(USER), cosmos_msg).unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::RewardPoolNotFound { staked_token: cw20_contract.addr().to_string(), reward: NATIVE_DENOM.to_string() }
+        );
+
+        let hook_msg = Cw20HookMsg::FundRewards { staked_token: cw20_contract.addr().to_string() };
+        let msg = Cw20ExecuteMsg::Send { contract: deposit_contract.addr().to_string(), amount: Uint128::new(50), msg: to_binary(&hook_msg).unwrap() };
+        app.execute(Addr::unchecked(USER), reward_contract.call(msg).unwrap()).unwrap();
+
+        //nothing is emitted while nobody stakes
+        app.update_block(|block| block.height += 5);
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 100);
+        app.update_block(|block| block.height += 10);
+        assert_eq!(get_pending_rewards(&app, &deposit_contract, USER, &cw20_contract), vec![(reward, Uint128::new(50))]);
+
+        let before = get_cw20_balance(&app, &reward_contract, USER.to_string());
+        let msg = ExecuteMsg::ClaimRewards { staked_token: cw20_contract.addr().to_string() };
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+        let after = get_cw20_balance(&app, &reward_contract, USER.to_string());
+        assert_eq!(after.balance - before.balance, Uint128::new(50));
+    }
+
+    #[test]
+    fn emergency_withdraw_penalty_decays_toward_expiry() {
+        let (mut app, deposit_id, cw20_id, _cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 1000);
+        let msg = ExecuteMsg::EmergencyWithdrawCw20 { address: cw20_contract.addr().to_string(), amount: Uint128::new(1400) };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg.clone(), vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::EarlyWithdrawDisabled {});
+
+        let early = EarlyWithdraw { max_penalty: Decimal::percent(150), recipient: PenaltyRecipient::Treasury { address: "treasury".to_string() } };
+        let update = ExecuteMsg::UpdateEarlyWithdraw { early_withdraw: Some(early) };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(update.clone(), vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::Unauthorized {});
+        let err = app.execute(Addr::unchecked(ADMIN), deposit_contract.call(update, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::InvalidPenalty {});
+
+        let early = EarlyWithdraw { max_penalty: Decimal::percent(50), recipient: PenaltyRecipient::Treasury { address: "treasury".to_string() } };
+        let update = ExecuteMsg::UpdateEarlyWithdraw { early_withdraw: Some(early.clone()) };
+        app.execute(Addr::unchecked(ADMIN), deposit_contract.call(update, vec![]).unwrap()).unwrap();
+        assert_eq!(get_config(&app, &deposit_contract).early_withdraw, Some(early));
+
+        let zero = ExecuteMsg::EmergencyWithdrawCw20 { address: cw20_contract.addr().to_string(), amount: Uint128::zero() };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(zero, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::ZeroAmount {});
+
+        //the first lot unlocks as the second one is halfway through its lock
+        app.update_block(|block| block.height += 10);
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 1000);
+        app.update_block(|block| block.height += 10);
+
+        //matured lots go first and free, 400 of the locked lot pay half of the 50% max
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_cw20_balance(&app, &cw20_contract, USER.to_string()).balance, Uint128::new(9300));
+        assert_eq!(get_cw20_balance(&app, &cw20_contract, "treasury".to_string()).balance, Uint128::new(100));
+        let lots = get_cw20_lots(&app, &deposit_contract, &cw20_contract);
+        assert_eq!(lots.lots.len(), 1);
+        assert_eq!(lots.locked, Uint128::new(600));
+        assert_eq!(get_totals(&app, &deposit_contract, AssetKind::Cw20)[0].1, AssetTotal { amount: Uint128::new(600), depositors: 1 });
+
+        let update = ExecuteMsg::UpdateEarlyWithdraw { early_withdraw: None };
+        app.execute(Addr::unchecked(ADMIN), deposit_contract.call(update, vec![]).unwrap()).unwrap();
+        let msg = ExecuteMsg::EmergencyWithdrawCw20 { address: cw20_contract.addr().to_string(), amount: Uint128::new(600) };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::EarlyWithdrawDisabled {});
+    }
+
+    #[test]
+    fn emergency_withdraw_penalty_is_shared_with_remaining_stakers() {
+        let (mut app, deposit_id, cw20_id, _cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        let msg = Cw20ExecuteMsg::Transfer { recipient: OTHER_USER.to_string(), amount: Uint128::new(1000) };
+        app.execute(Addr::unchecked(USER), cw20_contract.call(msg).unwrap()).unwrap();
+
+        let early = EarlyWithdraw { max_penalty: Decimal::percent(50), recipient: PenaltyRecipient::Stakers {} };
+        let update = ExecuteMsg::UpdateEarlyWithdraw { early_withdraw: Some(early) };
+        app.execute(Addr::unchecked(ADMIN), deposit_contract.call(update, vec![]).unwrap()).unwrap();
+
+        cw20_deposit_as(&mut app, OTHER_USER, &deposit_contract, &cw20_contract, 250);
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 200);
+        app.update_block(|block| block.height += 10);
+
+        let msg = ExecuteMsg::EmergencyWithdrawCw20 { address: cw20_contract.addr().to_string(), amount: Uint128::new(200) };
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_cw20_balance(&app, &cw20_contract, USER.to_string()).balance, Uint128::new(8950));
+
+        let reward = RewardToken::Cw20 { contract: cw20_contract.addr().to_string() };
+        assert_eq!(get_pending_rewards(&app, &deposit_contract, OTHER_USER, &cw20_contract), vec![(reward, Uint128::new(50))]);
+        let msg = ExecuteMsg::ClaimRewards { staked_token: cw20_contract.addr().to_string() };
+        app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_cw20_balance(&app, &cw20_contract, OTHER_USER.to_string()).balance, Uint128::new(800));
+    }
+
+    #[test]
+    fn emergency_withdraw_of_a_full_penalty_without_stakers_pays_the_admin() {
+        let (mut app, deposit_id, cw20_id, _cw721_id) = store_code();
+        let msg = InstantiateMsg { admin: Some("admin".to_string()), default_lock_duration: None, cw20_lock_durations: vec![], open: true, allowed_tokens: vec![] };
+        let deposit_contract = deposit_instantiate_with_msg(&mut app, deposit_id, msg);
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+
+        let early = EarlyWithdraw { max_penalty: Decimal::one(), recipient: PenaltyRecipient::Stakers {} };
+        let update = ExecuteMsg::UpdateEarlyWithdraw { early_withdraw: Some(early) };
+        app.execute(Addr::unchecked("admin"), deposit_contract.call(update, vec![]).unwrap()).unwrap();
+
+        //right after the deposit the whole amount is penalty and the withdrawer was the only staker
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 100);
+        let msg = ExecuteMsg::EmergencyWithdrawCw20 { address: cw20_contract.addr().to_string(), amount: Uint128::new(100) };
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_cw20_balance(&app, &cw20_contract, USER.to_string()).balance, Uint128::new(9900));
+        assert_eq!(get_cw20_balance(&app, &cw20_contract, "admin".to_string()).balance, Uint128::new(100));
+        assert_eq!(get_cw20_balance(&app, &cw20_contract, deposit_contract.addr().to_string()).balance, Uint128::zero());
+        let pools: RewardPoolsResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::RewardPools { staked_token: cw20_contract.addr().to_string(), start_after: None, limit: None })
+            .unwrap();
+        assert!(pools.pools.is_empty());
+    }
+
+    #[test]
+    fn emergency_withdraw_waits_out_the_unbonding_period() {
+        let (mut app, deposit_id, cw20_id, _cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+
+        let early = EarlyWithdraw { max_penalty: Decimal::percent(50), recipient: PenaltyRecipient::Treasury { address: "treasury".to_string() } };
+        let update = ExecuteMsg::UpdateEarlyWithdraw { early_withdraw: Some(early) };
+        app.execute(Addr::unchecked(ADMIN), deposit_contract.call(update, vec![]).unwrap()).unwrap();
+        let update = ExecuteMsg::UpdateUnbondingPeriod { unbonding_period: Some(Duration::Height(10)) };
+        app.execute(Addr::unchecked(ADMIN), deposit_contract.call(update, vec![]).unwrap()).unwrap();
+
+        //the penalty is paid right away, the rest is queued like an unbond
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 1000);
+        let withdrawn = app.block_info().height;
+        let msg = ExecuteMsg::EmergencyWithdrawCw20 { address: cw20_contract.addr().to_string(), amount: Uint128::new(1000) };
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_cw20_balance(&app, &cw20_contract, USER.to_string()).balance, Uint128::new(9000));
+        assert_eq!(get_cw20_balance(&app, &cw20_contract, "treasury".to_string()).balance, Uint128::new(500));
+
+        let claims: ClaimsResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Claims { address: USER.to_string(), start_after: None, limit: None })
+            .unwrap();
+        assert_eq!(claims.claims, vec![(1, Cw20Claim { contract: cw20_contract.addr().to_string(), amount: Uint128::new(500), release_at: Expiration::AtHeight(withdrawn + 10) })]);
+
+        let claim = ExecuteMsg::ClaimUnbonded {};
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(claim.clone(), vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::NoMaturedClaims {});
+
+        app.update_block(|block| block.height += 10);
+        app.execute(Addr::unchecked(USER), deposit_contract.call(claim, vec![]).unwrap()).unwrap();
+        assert_eq!(get_cw20_balance(&app, &cw20_contract, USER.to_string()).balance, Uint128::new(9500));
+    }
+
+    #[test]
+    fn unbonded_cw20_is_claimable_after_the_unbonding_period() {
+        let (mut app, deposit_id, cw20_id, _cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 500);
+        app.update_block(|block| block.height += 20);
+
+        let update = ExecuteMsg::UpdateUnbondingPeriod { unbonding_period: Some(Duration::Height(10)) };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(update.clone(), vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::Unauthorized {});
+        app.execute(Addr::unchecked(ADMIN), deposit_contract.call(update, vec![]).unwrap()).unwrap();
+        assert_eq!(get_config(&app, &deposit_contract).unbonding_period, Some(Duration::Height(10)));
+
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Cw20 { contract: cw20_contract.addr().to_string() }, amount: Uint128::new(200), recipient: None, msg: None};
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::UnbondingRequired {});
+
+        let msg = ExecuteMsg::UnbondCw20 { address: cw20_contract.addr().to_string(), amount: Uint128::zero() };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::ZeroAmount {});
+
+        let unbonded = app.block_info().height;
+        let msg = ExecuteMsg::UnbondCw20 { address: cw20_contract.addr().to_string(), amount: Uint128::new(200) };
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_totals(&app, &deposit_contract, AssetKind::Cw20)[0].1, AssetTotal { amount: Uint128::new(300), depositors: 1 });
+
+        let claim = ExecuteMsg::ClaimUnbonded {};
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(claim.clone(), vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::NoMaturedClaims {});
+
+        app.update_block(|block| block.height += 5);
+        let msg = ExecuteMsg::UnbondCw20 { address: cw20_contract.addr().to_string(), amount: Uint128::new(100) };
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+
+        let get_claims = |app: &App| -> ClaimsResponse {
+            app.wrap()
+                .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Claims { address: USER.to_string(), start_after: None, limit: None })
+                .unwrap()
+        };
+        let claims = get_claims(&app).claims;
+        assert_eq!(claims.len(), 2);
+        assert_eq!(claims[0], (1, Cw20Claim { contract: cw20_contract.addr().to_string(), amount: Uint128::new(200), release_at: Expiration::AtHeight(unbonded + 10) }));
+        assert_eq!(claims[1].1.release_at, Expiration::AtHeight(unbonded + 15));
+        let page: ClaimsResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Claims { address: USER.to_string(), start_after: Some(1), limit: Some(1) })
+            .unwrap();
+        assert_eq!(page.claims, vec![claims[1].clone()]);
+        assert_eq!(page.next_start_after, Some(2));
+
+        //only released claims are paid
+        app.update_block(|block| block.height += 5);
+        app.execute(Addr::unchecked(USER), deposit_contract.call(claim.clone(), vec![]).unwrap()).unwrap();
+        assert_eq!(get_cw20_balance(&app, &cw20_contract, USER.to_string()).balance, Uint128::new(9700));
+        assert_eq!(get_claims(&app).claims.len(), 1);
+
+        app.update_block(|block| block.height += 5);
+        app.execute(Addr::unchecked(USER), deposit_contract.call(claim, vec![]).unwrap()).unwrap();
+        assert_eq!(get_cw20_balance(&app, &cw20_contract, USER.to_string()).balance, Uint128::new(9800));
+        assert_eq!(get_claims(&app).claims, vec![]);
+    }
+
+    #[test]
+    fn deposits_can_be_transferred_inside_the_ledger() {
+        let (mut app, deposit_id, cw20_id, _cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+
+        let msg = ExecuteMsg::Deposit {};
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![coin(400, NATIVE_DENOM)]).unwrap()).unwrap();
+
+        let native = AssetInfo::Native { denom: NATIVE_DENOM.to_string() };
+        let msg = ExecuteMsg::TransferDeposit { recipient: OTHER_USER.to_string(), asset: native.clone(), amount: Uint128::zero() };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::ZeroAmount {});
+        let msg = ExecuteMsg::TransferDeposit { recipient: OTHER_USER.to_string(), asset: native.clone(), amount: Uint128::new(500) };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InsufficientBalance { asset: NATIVE_DENOM.to_string(), available: Uint128::new(400), requested: Uint128::new(500) }
+        );
+
+        let msg = ExecuteMsg::TransferDeposit { recipient: OTHER_USER.to_string(), asset: native, amount: Uint128::new(150) };
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_deposits(&app, &deposit_contract).deposits[0].1.coins, coin(250, NATIVE_DENOM));
+        assert_eq!(get_totals(&app, &deposit_contract, AssetKind::Native)[0].1, AssetTotal { amount: Uint128::new(400), depositors: 2 });
+
+        //the recipient owns the balance like any other deposit
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Native { denom: NATIVE_DENOM.to_string() }, amount: Uint128::new(150), recipient: None, msg: None };
+        app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_balance(&app, OTHER_USER.to_string(), NATIVE_DENOM.to_string()).amount, Uint128::new(150));
+
+        //locked cw20 stays with its owner
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 100);
+        let cw20 = AssetInfo::Cw20 { contract: cw20_contract.addr().to_string() };
+        let msg = ExecuteMsg::TransferDeposit { recipient: OTHER_USER.to_string(), asset: cw20, amount: Uint128::new(60) };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg.clone(), vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::StakeDurationNotPassed {});
+
+        app.update_block(|block| block.height += 20);
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_cw20_deposits(&app, &deposit_contract).deposits[0].1.amount, Uint128::new(40));
+        assert_eq!(get_totals(&app, &deposit_contract, AssetKind::Cw20)[0].1, AssetTotal { amount: Uint128::new(100), depositors: 2 });
+
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Cw20 { contract: cw20_contract.addr().to_string() }, amount: Uint128::new(60), recipient: None, msg: None};
+        app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_cw20_balance(&app, &cw20_contract, OTHER_USER.to_string()).balance, Uint128::new(60));
+    }
+
+    #[test]
+    fn transferred_cw20_is_merged_into_one_unlocked_lot() {
+        let (mut app, deposit_id, cw20_id, _cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 100);
+        app.update_block(|block| block.height += 20);
+
+        //dust sent back and forth does not grow the recipient's lots
+        let cw20 = AssetInfo::Cw20 { contract: cw20_contract.addr().to_string() };
+        for _ in 0..3 {
+            let msg = ExecuteMsg::TransferDeposit { recipient: OTHER_USER.to_string(), asset: cw20.clone(), amount: Uint128::new(1) };
+            app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        }
+        for _ in 0..3 {
+            let msg = ExecuteMsg::TransferDeposit { recipient: USER.to_string(), asset: cw20.clone(), amount: Uint128::new(1) };
+            app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        }
+        let lots = get_cw20_lots(&app, &deposit_contract, &cw20_contract);
+        assert_eq!(lots.lots.iter().map(|(lot_id, lot)| (*lot_id, lot.amount)).collect::<Vec<_>>(), vec![(0, Uint128::new(3)), (1, Uint128::new(97))]);
+        assert_eq!(lots.unlocked, Uint128::new(100));
+
+        let msg = ExecuteMsg::Withdraw { asset: cw20, amount: Uint128::new(100), recipient: None, msg: None };
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_cw20_balance(&app, &cw20_contract, USER.to_string()).balance, Uint128::new(10000));
+        assert_eq!(get_cw20_lots(&app, &deposit_contract, &cw20_contract).lots, vec![]);
+    }
+
+    #[test]
+    fn spenders_withdraw_within_their_allowance() {
+        let (mut app, deposit_id, cw20_id, _cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+
+        let msg = ExecuteMsg::Deposit {};
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![coin(400, NATIVE_DENOM)]).unwrap()).unwrap();
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 100);
+        app.update_block(|block| block.height += 20);
+        let height = app.block_info().height;
+
+        let native = AssetInfo::Native { denom: NATIVE_DENOM.to_string() };
+        let msg = ExecuteMsg::IncreaseAllowance { spender: USER.to_string(), asset: native.clone(), amount: Uint128::new(100), expires: None };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::CannotSetOwnAccount {});
+        let msg = ExecuteMsg::IncreaseAllowance { spender: OTHER_USER.to_string(), asset: native.clone(), amount: Uint128::new(100), expires: Some(Expiration::AtHeight(height)) };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::InvalidExpiration {});
+
+        let msg = ExecuteMsg::IncreaseAllowance { spender: OTHER_USER.to_string(), asset: native.clone(), amount: Uint128::new(100), expires: Some(Expiration::AtHeight(height + 5)) };
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg.clone(), vec![]).unwrap()).unwrap();
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        let msg = ExecuteMsg::IncreaseAllowance { spender: OTHER_USER.to_string(), asset: native.clone(), amount: Uint128::MAX, expires: Some(Expiration::AtHeight(height + 5)) };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::Overflow(OverflowError::new(OverflowOperation::Add, Uint128::new(200), Uint128::MAX))
+        );
+        let msg = ExecuteMsg::DecreaseAllowance { spender: OTHER_USER.to_string(), asset: native.clone(), amount: Uint128::new(50), expires: None };
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+
+        let allowances: AllowancesResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Allowances { owner: USER.to_string(), start_after: None, limit: Some(1) })
+            .unwrap();
+        assert_eq!(
+            allowances.allowances,
+            vec![(OTHER_USER.to_string(), Allowance { asset: native.clone(), amount: Uint128::new(150), expires: Expiration::AtHeight(height + 5) })]
+        );
+        assert_eq!(allowances.next_start_after, Some((OTHER_USER.to_string(), native.key())));
+        let allowances: AllowancesResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Allowances { owner: USER.to_string(), start_after: allowances.next_start_after, limit: None })
+            .unwrap();
+        assert_eq!(allowances.allowances, vec![]);
+
+        let msg = ExecuteMsg::WithdrawFrom { owner: USER.to_string(), asset: native.clone(), amount: Uint128::new(200), recipient: "recipient".to_string() };
+        let err = app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InsufficientAllowance { available: Uint128::new(150), requested: Uint128::new(200) }
+        );
+        let msg = ExecuteMsg::WithdrawFrom { owner: USER.to_string(), asset: native.clone(), amount: Uint128::new(100), recipient: "recipient".to_string() };
+        app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_balance(&app, "recipient".to_string(), NATIVE_DENOM.to_string()).amount, Uint128::new(100));
+        assert_eq!(get_deposits(&app, &deposit_contract).deposits[0].1.coins, coin(300, NATIVE_DENOM));
+
+        //what is left of the allowance lapses at its expiration
+        app.update_block(|block| block.height += 5);
+        let msg = ExecuteMsg::WithdrawFrom { owner: USER.to_string(), asset: native, amount: Uint128::new(50), recipient: OTHER_USER.to_string() };
+        let err = app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::AllowanceExpired {});
+
+        let cw20 = AssetInfo::Cw20 { contract: cw20_contract.addr().to_string() };
+        let msg = ExecuteMsg::IncreaseAllowance { spender: OTHER_USER.to_string(), asset: cw20.clone(), amount: Uint128::new(60), expires: None };
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        let msg = ExecuteMsg::WithdrawFrom { owner: USER.to_string(), asset: cw20.clone(), amount: Uint128::new(60), recipient: OTHER_USER.to_string() };
+        app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg.clone(), vec![]).unwrap()).unwrap();
+        assert_eq!(get_cw20_balance(&app, &cw20_contract, OTHER_USER.to_string()).balance, Uint128::new(60));
+        assert_eq!(get_cw20_deposits(&app, &deposit_contract).deposits[0].1.amount, Uint128::new(40));
+
+        //a used up allowance is removed
+        let err = app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::NoAllowance {});
+    }
+
+    #[test]
+    fn withdrawals_go_to_a_recipient_or_into_a_contract() {
+        let (mut app, deposit_id, cw20_id, cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let other_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        let cw721_contract = cw721_instantiate(&mut app, cw721_id, "NFT".to_string(), "NFT".to_string(), USER.to_string());
+        let first = deposit_contract.addr().to_string();
+
+        let msg = ExecuteMsg::Deposit {};
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![coin(300, NATIVE_DENOM)]).unwrap()).unwrap();
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Native { denom: NATIVE_DENOM.to_string() }, amount: Uint128::new(100), recipient: Some("recipient".to_string()), msg: None };
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_balance(&app, "recipient".to_string(), NATIVE_DENOM.to_string()).amount, Uint128::new(100));
+
+        //natives cannot carry a msg, it would be executed as the deposit contract
+        let transfer = Cw20ExecuteMsg::Transfer { recipient: "thief".to_string(), amount: Uint128::new(100) };
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Native { denom: NATIVE_DENOM.to_string() }, amount: Uint128::new(100), recipient: Some(cw20_contract.addr().to_string()), msg: Some(to_binary(&transfer).unwrap()) };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::NativeMsgUnsupported {});
+
+        //cw20 and cw721 can be deposited straight into another deposit contract
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 100);
+        app.update_block(|block| block.height += 20);
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Cw20 { contract: cw20_contract.addr().to_string() }, amount: Uint128::new(100), recipient: Some(other_contract.addr().to_string()), msg: Some(to_binary(&Cw20HookMsg::Deposit {}).unwrap()) };
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        let deposits: Cw20DepositResponse = app.wrap()
+            .query_wasm_smart(other_contract.addr(), &QueryMsg::Cw20Deposits { address: first.clone(), start_after: None, limit: None })
+            .unwrap();
+        assert_eq!(deposits.deposits[0].1.amount, Uint128::new(100));
+
+        mint_and_deposit_nft(&mut app, &deposit_contract, &cw721_contract, "0".to_string());
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Cw721 { contract: cw721_contract.addr().to_string(), token_id: "0".to_string() }, amount: Uint128::one(), recipient: Some(other_contract.addr().to_string()), msg: Some(to_binary(&Cw721HookMsg::Deposit {}).unwrap()) };
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_owner_of(&app, &cw721_contract, "0".to_string()).owner, other_contract.addr().to_string());
+        assert_eq!(get_cw721_deposits_by_owner(&app, &other_contract, first).deposits.len(), 1);
+    }
+
+    fn get_asset_balance(app: &App, deposit_contract: &DepositContract, address: &str, asset: &AssetInfo) -> Uint128 {
+        let res: crate::msg::BalanceResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Balance { address: address.to_string(), asset: asset.clone() })
+            .unwrap();
+        assert_eq!(&res.asset, asset);
+        res.amount
+    }
+
+    #[test]
+    fn every_asset_kind_shares_one_balance_and_withdraw_surface() {
+        let (mut app, deposit_id, cw20_id, cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        let cw721_contract = cw721_instantiate(&mut app, cw721_id, "NFT".to_string(), "NFT".to_string(), USER.to_string());
+        let native = AssetInfo::Native { denom: NATIVE_DENOM.to_string() };
+        let cw20 = AssetInfo::Cw20 { contract: cw20_contract.addr().to_string() };
+        let nft = AssetInfo::Cw721 { contract: cw721_contract.addr().to_string(), token_id: "0".to_string() };
+
+        let msg = ExecuteMsg::Deposit {};
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![coin(300, NATIVE_DENOM)]).unwrap()).unwrap();
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 200);
+        mint_and_deposit_nft(&mut app, &deposit_contract, &cw721_contract, "0".to_string());
+        assert_eq!(get_asset_balance(&app, &deposit_contract, USER, &native), Uint128::new(300));
+        assert_eq!(get_asset_balance(&app, &deposit_contract, USER, &cw20), Uint128::new(200));
+        assert_eq!(get_asset_balance(&app, &deposit_contract, USER, &nft), Uint128::one());
+        assert_eq!(get_asset_balance(&app, &deposit_contract, OTHER_USER, &nft), Uint128::zero());
+
+        //an nft only ever moves as a single unit
+        let msg = ExecuteMsg::TransferDeposit { recipient: OTHER_USER.to_string(), asset: nft.clone(), amount: Uint128::new(2) };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::InvalidNftAmount {});
+
+        let msg = ExecuteMsg::TransferDeposit { recipient: OTHER_USER.to_string(), asset: nft.clone(), amount: Uint128::one() };
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_asset_balance(&app, &deposit_contract, USER, &nft), Uint128::zero());
+        assert_eq!(get_asset_balance(&app, &deposit_contract, OTHER_USER, &nft), Uint128::one());
+
+        let msg = ExecuteMsg::Withdraw { asset: nft.clone(), amount: Uint128::one(), recipient: None, msg: None };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::InvalidOwner {});
+
+        //the new owner can let USER pull it out through an allowance
+        let msg = ExecuteMsg::IncreaseAllowance { spender: USER.to_string(), asset: nft.clone(), amount: Uint128::one(), expires: None };
+        app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        let msg = ExecuteMsg::WithdrawFrom { owner: OTHER_USER.to_string(), asset: nft.clone(), amount: Uint128::one(), recipient: USER.to_string() };
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_owner_of(&app, &cw721_contract, "0".to_string()).owner, USER.to_string());
+        assert_eq!(get_asset_balance(&app, &deposit_contract, OTHER_USER, &nft), Uint128::zero());
+        assert_eq!(get_totals(&app, &deposit_contract, AssetKind::Cw721)[0].1, AssetTotal { amount: Uint128::zero(), depositors: 0 });
+    }
+
+    fn get_pending_nft_rewards(app: &App, deposit_contract: &DepositContract, owner: &str) -> Vec<(RewardToken, Uint128)> {
+        let res: PendingRewardsResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::PendingNftRewards { owner: owner.to_string() })
+            .unwrap();
+        res.rewards
+    }
+
+    #[test]
+    fn nft_rewards_accrue_per_escrowed_token() {
+        let (mut app, deposit_id, _cw20_id, cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw721_contract = cw721_instantiate(&mut app, cw721_id, "NFT".to_string(), "NFT".to_string(), USER.to_string());
+        let reward = RewardToken::Native { denom: NATIVE_DENOM.to_string() };
+
+        let msg = ExecuteMsg::SetNftRewardPool { collection: cw721_contract.addr().to_string(), reward: reward.clone(), reward_per_block: Uint128::new(2) };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg.clone(), vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::Unauthorized {});
+        app.execute(Addr::unchecked(ADMIN), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+
+        let msg = ExecuteMsg::FundNftRewards { collection: cw721_contract.addr().to_string() };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg.clone(), vec![coin(500, OTHER_DENOM)]).unwrap()).unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::NftRewardPoolNotFound { collection: cw721_contract.addr().to_string(), reward: OTHER_DENOM.to_string() }
+        );
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![coin(500, NATIVE_DENOM)]).unwrap()).unwrap();
+
+        //every escrowed token earns the full rate
+        mint_and_deposit_nft(&mut app, &deposit_contract, &cw721_contract, "0".to_string());
+        app.update_block(|block| block.height += 10);
+        assert_eq!(get_pending_nft_rewards(&app, &deposit_contract, USER), vec![(reward.clone(), Uint128::new(20))]);
+        mint_and_deposit_nft(&mut app, &deposit_contract, &cw721_contract, "1".to_string());
+        app.update_block(|block| block.height += 10);
+        assert_eq!(get_pending_nft_rewards(&app, &deposit_contract, USER), vec![(reward.clone(), Uint128::new(60))]);
+
+        let before = get_balance(&app, USER.to_string(), NATIVE_DENOM.to_string());
+        let msg = ExecuteMsg::ClaimNftRewards {};
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg.clone(), vec![]).unwrap()).unwrap();
+        let after = get_balance(&app, USER.to_string(), NATIVE_DENOM.to_string());
+        assert_eq!(after.amount - before.amount, Uint128::new(60));
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::NoRewardsToClaim {});
+
+        //withdrawing pays out what the token earned and stops it earning
+        app.update_block(|block| block.height += 5);
+        let before = get_balance(&app, USER.to_string(), NATIVE_DENOM.to_string());
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Cw721 { contract: cw721_contract.addr().to_string(), token_id: "0".to_string() }, amount: Uint128::one(), recipient: None, msg: None};
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        let after = get_balance(&app, USER.to_string(), NATIVE_DENOM.to_string());
+        assert_eq!(after.amount - before.amount, Uint128::new(10));
+        assert_eq!(get_pending_nft_rewards(&app, &deposit_contract, USER), vec![(reward.clone(), Uint128::new(10))]);
+
+        app.update_block(|block| block.height += 5);
+        assert_eq!(get_pending_nft_rewards(&app, &deposit_contract, USER), vec![(reward, Uint128::new(20))]);
+        let pools: RewardPoolsResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::NftRewardPools { collection: cw721_contract.addr().to_string(), start_after: None, limit: None })
+            .unwrap();
+        assert_eq!(pools.pools[0].1.reward_per_block, Uint128::new(2));
+    }
+
+    #[test]
+    fn deposits_at_past_heights() {
+        let (mut app, deposit_id, cw20_id, _cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+
+        let start = app.block_info().height;
+        let msg = ExecuteMsg::Deposit {};
+        let cosmos_msg = deposit_contract.call(msg, vec![coin(300, NATIVE_DENOM)]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 100);
+        app.update_block(|block| block.height += 1);
+
+        let msg = ExecuteMsg::Deposit {};
+        let cosmos_msg = deposit_contract.call(msg, vec![coin(200, NATIVE_DENOM), coin(50, OTHER_DENOM)]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 100);
+        app.update_block(|block| block.height += 1);
+
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Native { denom: NATIVE_DENOM.to_string() }, amount: Uint128::new(500), recipient: None, msg: None };
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+        app.update_block(|block| block.height += 1);
+
+        let deposits_at = |height: u64, start_after: Option<String>, limit: Option<u32>| -> DepositResponse {
+            app.wrap()
+                .query_wasm_smart(deposit_contract.addr(), &QueryMsg::DepositsAt { address: USER.to_string(), height, start_after, limit })
+                .unwrap()
+        };
+        assert!(deposits_at(start, None, None).deposits.is_empty());
+        let at = deposits_at(start + 1, None, None);
+        assert_eq!(at.deposits.len(), 1);
+        assert_eq!(at.deposits[0].1.coins, coin(300, NATIVE_DENOM));
+        let at = deposits_at(start + 2, None, None);
+        assert_eq!(at.deposits[0].1.coins, coin(500, NATIVE_DENOM));
+        assert_eq!(at.deposits[1].1.coins, coin(50, OTHER_DENOM));
+        let at = deposits_at(start + 3, None, None);
+        assert_eq!(at.deposits[0].1.coins, coin(0, NATIVE_DENOM));
+
+        let page = deposits_at(start + 2, None, Some(1));
+        assert_eq!(page.deposits[0].0, NATIVE_DENOM.to_string());
+        let page = deposits_at(start + 2, page.next_start_after, Some(1));
+        assert_eq!(page.deposits[0].0, OTHER_DENOM.to_string());
+        //a page looks at limit denoms, other denom wasn't deposited yet at start + 1
+        let page = deposits_at(start + 1, Some(NATIVE_DENOM.to_string()), Some(1));
+        assert!(page.deposits.is_empty());
+        assert_eq!(page.next_start_after, Some(OTHER_DENOM.to_string()));
+
+        //other owners' history is not mixed in
+        let res: DepositResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::DepositsAt { address: OTHER_USER.to_string(), height: start + 2, start_after: None, limit: None })
+            .unwrap();
+        assert!(res.deposits.is_empty());
+
+        let cw20_at = |height: u64| -> Cw20DepositResponse {
+            app.wrap()
+                .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Cw20DepositsAt { address: USER.to_string(), height, start_after: None, limit: None })
+                .unwrap()
+        };
+        assert!(cw20_at(start).deposits.is_empty());
+        assert_eq!(cw20_at(start + 1).deposits[0].1.amount, Uint128::new(100));
+        assert_eq!(cw20_at(start + 2).deposits[0].1.amount, Uint128::new(200));
+        assert_eq!(cw20_at(start + 2).deposits[0].0, cw20_contract.addr().to_string());
+    }
+
+    #[test]
+    fn migrate_rejects_foreign_contracts_and_downgrades() {
+        let (mut app, _deposit_id, _cw20_id, _cw721_id) = store_code();
+        let legacy_id = app.store_code(contract_legacy_deposit());
+        let deposit_id = app.store_code(contract_deposit_cw20());
+
+        let foreign = legacy_instantiate(&mut app, legacy_id, "crates.io:cw20-base", "0.1.0");
+        let err = app.migrate_contract(Addr::unchecked(ADMIN), foreign.addr(), &migrate_msg(), deposit_id).unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::CannotMigrate { previous_contract: "crates.io:cw20-base".to_string() }
+        );
+
+        let newer = legacy_instantiate(&mut app, legacy_id, "deposit-cw20-example", "9.0.0");
+        let err = app.migrate_contract(Addr::unchecked(ADMIN), newer.addr(), &migrate_msg(), deposit_id).unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::CannotMigrateVersion { previous_version: "9.0.0".to_string(), new_version: env!("CARGO_PKG_VERSION").to_string() }
+        );
+    }
+
+    #[test]
+    fn mint_then_deposit_nft_then_withdraw_nft_back_to_owner() {
+        let (mut app, deposit_id, _cw20_id, cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw721_contract = cw721_instantiate(&mut app, cw721_id, "NFT".to_string(), "NFT".to_string(), USER.to_string());
+
+        //mint NFT to User
+        let mint_msg = nft::contract::MintMsg{token_id:"0".to_string(), owner:USER.to_string(), token_uri:Some("url".to_string()), extension:None };
+        let msg = nft::contract::ExecuteMsg::Mint(mint_msg);
+        let cosmos_msg = cw721_contract.call(msg).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+
+        //check to see if User is the owner.
+        let owner = get_owner_of(&app, &cw721_contract, "0".to_string());
+        assert_eq!(owner.owner, USER.to_string());
+        println!("{:?}", owner);
+
+        //deposit NFT to Deposit Contract
+        let hook_msg = Cw721HookMsg::Deposit { };
+        let msg = nft::contract::ExecuteMsg::SendNft { contract: deposit_contract.addr().to_string(), token_id: "0".to_string(), msg: to_binary(&hook_msg).unwrap() };
+        let cosmos_msg = cw721_contract.call(msg).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+
+        //check to see if Deposit Contract is the owner.
+        let owner = get_owner_of(&app, &cw721_contract, "0".to_string());
+        assert_eq!(owner.owner, deposit_contract.addr().to_string());
+        println!("{:?}", owner);
+
+
+        //withdraw NFT from Deposit Contract
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Cw721 { contract: cw721_contract.addr().to_string(), token_id: "0".to_string() }, amount: Uint128::one(), recipient: None, msg: None};
+        let execute_msg = WasmMsg::Execute { contract_addr: deposit_contract.addr().to_string(), msg: to_binary(&msg).unwrap(), funds: vec![] };
+        app.execute(Addr::unchecked(USER), execute_msg.into()).unwrap();
+
+        //check to see if User is the owner.
+        let owner = get_owner_of(&app, &cw721_contract, "0".to_string());
+        assert_eq!(owner.owner, USER.to_string());
+        println!("{:?}", owner);
+    }
+
+    #[test]
+    fn withdraw_nft_only_by_owner_or_approved_operator() {
+        let (mut app, deposit_id, _cw20_id, cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw721_contract = cw721_instantiate(&mut app, cw721_id, "NFT".to_string(), "NFT".to_string(), USER.to_string());
+
+        mint_and_deposit_nft(&mut app, &deposit_contract, &cw721_contract, "0".to_string());
+        mint_and_deposit_nft(&mut app, &deposit_contract, &cw721_contract, "1".to_string());
+
+        let deposits = get_cw721_deposits_by_owner(&app, &deposit_contract, USER.to_string());
+        assert_eq!(deposits.deposits.len(), 2);
+
+        //a stranger cannot withdraw the nft
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Cw721 { contract: cw721_contract.addr().to_string(), token_id: "0".to_string() }, amount: Uint128::one(), recipient: None, msg: None};
+        let cosmos_msg = deposit_contract.call(msg.clone(), vec![]).unwrap();
+        let err = app.execute(Addr::unchecked(OTHER_USER), cosmos_msg).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::InvalidOwner {});
+
+        //withdrawing a token that was never deposited fails cleanly
+        let missing = ExecuteMsg::Withdraw { asset: AssetInfo::Cw721 { contract: cw721_contract.addr().to_string(), token_id: "2".to_string() }, amount: Uint128::one(), recipient: None, msg: None};
+        let cosmos_msg = deposit_contract.call(missing, vec![]).unwrap();
+        let err = app.execute(Addr::unchecked(USER), cosmos_msg).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::NoCw721ToWithdraw {});
+
+        let height = app.block_info().height;
+        let expired = ExecuteMsg::ApproveNftOperator { operator: OTHER_USER.to_string(), expires: Some(Expiration::AtHeight(height)) };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(expired, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::Expired {});
+
+        //once approved the operator can withdraw
+        let approve = ExecuteMsg::ApproveNftOperator { operator: OTHER_USER.to_string(), expires: None };
+        let cosmos_msg = deposit_contract.call(approve, vec![]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(OTHER_USER), cosmos_msg).unwrap();
+
+        let owner = get_owner_of(&app, &cw721_contract, "0".to_string());
+        assert_eq!(owner.owner, OTHER_USER.to_string());
+
+        //revoked operators lose access again
+        let revoke = ExecuteMsg::RevokeNftOperator { operator: OTHER_USER.to_string() };
+        let cosmos_msg = deposit_contract.call(revoke, vec![]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Cw721 { contract: cw721_contract.addr().to_string(), token_id: "1".to_string() }, amount: Uint128::one(), recipient: None, msg: None};
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        let err = app.execute(Addr::unchecked(OTHER_USER), cosmos_msg).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::InvalidOwner {});
+
+        let deposits = get_cw721_deposits_by_contract(&app, &deposit_contract, &cw721_contract);
+        assert_eq!(deposits.deposits.len(), 1);
+    }
+
+    #[test]
+    fn nft_deposit_listings_are_paginated() {
+        let (mut app, deposit_id, _cw20_id, cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw721_contract = cw721_instantiate(&mut app, cw721_id, "NFT".to_string(), "NFT".to_string(), USER.to_string());
+        let other_cw721_contract = cw721_instantiate(&mut app, cw721_id, "NFT2".to_string(), "NFT2".to_string(), USER.to_string());
+
+        for token_id in ["0", "1", "2"] {
+            mint_and_deposit_nft(&mut app, &deposit_contract, &cw721_contract, token_id.to_string());
+        }
+        mint_and_deposit_nft(&mut app, &deposit_contract, &other_cw721_contract, "0".to_string());
+
+        //owner listing spans both collections
+        let page = get_cw721_deposits_by_owner_page(&app, &deposit_contract, USER.to_string(), None, Some(3));
+        assert_eq!(page.deposits.len(), 3);
+        let cursor = page.next_start_after.clone().unwrap();
+        assert_eq!(cursor, page.deposits[2].0);
+
+        let page = get_cw721_deposits_by_owner_page(&app, &deposit_contract, USER.to_string(), Some(cursor), Some(3));
+        assert_eq!(page.deposits.len(), 1);
+        assert_eq!(page.next_start_after, None);
+
+        let all = get_cw721_deposits_by_owner(&app, &deposit_contract, USER.to_string());
+        assert_eq!(all.deposits.len(), 4);
+        assert_eq!(all.deposits[3].1, page.deposits[0].1);
+
+        let page: Cw721DepositResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Cw721DepositsByContract { contract_addr: cw721_contract.addr().to_string(), start_after: Some("0".to_string()), limit: Some(1) })
+            .unwrap();
+        assert_eq!(page.deposits[0].0, "1".to_string());
+        assert_eq!(page.next_start_after, Some("1".to_string()));
+
+        //limits above the maximum are clamped
+        let page: Cw721DepositResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Cw721DepositsByContract { contract_addr: cw721_contract.addr().to_string(), start_after: None, limit: Some(1000) })
+            .unwrap();
+        assert_eq!(page.deposits.len(), 3);
+        assert_eq!(page.next_start_after, None);
+    }
+
+    #[test]
+    fn nft_custody_at_past_heights() {
+        let (mut app, deposit_id, _cw20_id, cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw721_contract = cw721_instantiate(&mut app, cw721_id, "NFT".to_string(), "NFT".to_string(), USER.to_string());
+
+        let start = app.block_info().height;
+        mint_and_deposit_nft(&mut app, &deposit_contract, &cw721_contract, "0".to_string());
+        mint_and_deposit_nft(&mut app, &deposit_contract, &cw721_contract, "1".to_string());
+        app.update_block(|block| block.height += 5);
+
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Cw721 { contract: cw721_contract.addr().to_string(), token_id: "0".to_string() }, amount: Uint128::one(), recipient: None, msg: None};
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+        mint_and_deposit_nft(&mut app, &deposit_contract, &cw721_contract, "2".to_string());
+        app.update_block(|block| block.height += 1);
+
+        let deposit_at = |token_id: &str, height: u64| -> Cw721DepositAtResponse {
+            app.wrap()
+                .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Cw721DepositAt { contract_addr: cw721_contract.addr().to_string(), token_id: token_id.to_string(), height })
+                .unwrap()
+        };
+        assert_eq!(deposit_at("0", start).deposit, None);
+        assert_eq!(deposit_at("0", start + 1).deposit.unwrap().owner, USER.to_string());
+        assert_eq!(deposit_at("0", start + 6).deposit, None);
+        assert_eq!(deposit_at("2", start + 5).deposit, None);
+        assert_eq!(deposit_at("2", start + 6).deposit.unwrap().token_id, "2".to_string());
+
+        let owner_at = |height: u64, start_after: Option<(String, String)>, limit: Option<u32>| -> Cw721DepositsByOwnerResponse {
+            app.wrap()
+                .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Cw721DepositsByOwnerAt { address: USER.to_string(), height, start_after, limit })
+                .unwrap()
+        };
+        assert_eq!(owner_at(start, None, None).deposits.len(), 0);
+
+        let tokens = |res: &Cw721DepositsByOwnerResponse| -> Vec<String> { res.deposits.iter().map(|(_, d)| d.token_id.clone()).collect() };
+        assert_eq!(tokens(&owner_at(start + 1, None, None)), vec!["0".to_string(), "1".to_string()]);
+        assert_eq!(tokens(&owner_at(start + 6, None, None)), vec!["1".to_string(), "2".to_string()]);
+
+        //pages are bounded by the tokens looked at, token 0 is no longer held
+        let page = owner_at(start + 6, None, Some(1));
+        assert_eq!(tokens(&page), Vec::<String>::new());
+        let page = owner_at(start + 6, page.next_start_after, Some(1));
+        assert_eq!(tokens(&page), vec!["1".to_string()]);
+        let page = owner_at(start + 6, page.next_start_after, Some(2));
+        assert_eq!(tokens(&page), vec!["2".to_string()]);
+        assert_eq!(page.next_start_after, None);
+
+        //nothing held by other users
+        let res: Cw721DepositsByOwnerResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Cw721DepositsByOwnerAt { address: OTHER_USER.to_string(), height: start + 1, start_after: None, limit: None })
+            .unwrap();
+        assert_eq!(res.deposits.len(), 0);
+    }
+
+    #[test]
+    fn total_cw20_deposits_at_past_heights() {
+        let (mut app, deposit_id, cw20_id, _cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+
+        let start = app.block_info().height;
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 100);
+        app.update_block(|block| block.height += 1);
+        cw20_deposit(&mut app, &deposit_contract, &cw20_contract, 100);
+        app.update_block(|block| block.height += 20);
+
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Cw20 { contract: cw20_contract.addr().to_string() }, amount: Uint128::new(200), recipient: None, msg: None};
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+        app.update_block(|block| block.height += 1);
+
+        let total_at = |height: Option<u64>| -> TotalCw20DepositsResponse {
+            app.wrap()
+                .query_wasm_smart(deposit_contract.addr(), &QueryMsg::TotalCw20Deposits { height })
+                .unwrap()
+        };
+        assert_eq!(total_at(Some(start)).total, 0);
+        assert_eq!(total_at(Some(start + 1)).total, 1);
+        assert_eq!(total_at(Some(start + 2)).total, 2);
+        assert_eq!(total_at(Some(start + 22)).total, 1);
+        assert_eq!(total_at(None), TotalCw20DepositsResponse { total: 1, height: start + 22 });
+
+        let page: TotalCw20DepositsChangelogResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::TotalCw20DepositsChangelog { start_after: None, limit: Some(2) })
+            .unwrap();
+        assert_eq!(page.changelog, vec![(start, None), (start + 1, Some(1))]);
+        assert_eq!(page.next_start_after, Some(start + 1));
+
+        let page: TotalCw20DepositsChangelogResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::TotalCw20DepositsChangelog { start_after: page.next_start_after, limit: Some(2) })
+            .unwrap();
+        assert_eq!(page.changelog, vec![(start + 21, Some(2))]);
+        assert_eq!(page.next_start_after, None);
+    }
+
+    #[test]
+    fn nft_custody_is_recorded_in_changelog() {
+        let (mut app, deposit_id, _cw20_id, cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw721_contract = cw721_instantiate(&mut app, cw721_id, "NFT".to_string(), "NFT".to_string(), USER.to_string());
+
+        let deposit_height = app.block_info().height;
+        mint_and_deposit_nft(&mut app, &deposit_contract, &cw721_contract, "0".to_string());
+
+        app.update_block(|block| block.height += 5);
+        let withdraw_height = app.block_info().height;
+
+        let msg = ExecuteMsg::Withdraw { asset: AssetInfo::Cw721 { contract: cw721_contract.addr().to_string(), token_id: "0".to_string() }, amount: Uint128::one(), recipient: None, msg: None};
+        let cosmos_msg = deposit_contract.call(msg, vec![]).unwrap();
+        app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+
+        let changelog = get_cw721_deposit_changelog(&app, &deposit_contract, &cw721_contract, "0".to_string()).changelog;
+        assert_eq!(changelog.len(), 2);
+        assert_eq!(changelog[0], (deposit_height, None));
+        assert_eq!(changelog[1].0, withdraw_height);
+        assert_eq!(changelog[1].1.as_ref().unwrap().owner, USER.to_string());
+    }
+
+    fn cw1155_instantiate(app: &mut App) -> Addr {
+        let cw1155_id = app.store_code(contract_cw1155());
+        let msg = cw1155_example::contract::InstantiateMsg { minter: USER.to_string() };
+        let cw1155_contract = app.instantiate_contract(cw1155_id, Addr::unchecked(ADMIN), &msg, &[], "cw1155-example", None).unwrap();
+        let mint = |token_id: &str, value: u128| Cw1155ExecuteMsg::Mint { to: USER.to_string(), token_id: token_id.to_string(), value: Uint128::new(value), msg: None };
+        app.execute_contract(Addr::unchecked(USER), cw1155_contract.clone(), &mint("sword", 100), &[]).unwrap();
+        app.execute_contract(Addr::unchecked(USER), cw1155_contract.clone(), &mint("shield", 50), &[]).unwrap();
+        cw1155_contract
+    }
+
+    fn get_cw1155_balance(app: &App, cw1155_contract: &Addr, owner: &str, token_id: &str) -> Uint128 {
+        let res: cw1155::BalanceResponse = app.wrap()
+            .query_wasm_smart(cw1155_contract, &cw1155::Cw1155QueryMsg::Balance { owner: owner.to_string(), token_id: token_id.to_string() })
+            .unwrap();
+        res.balance
+    }
+
+    fn get_cw1155_deposits_by_owner(app: &App, deposit_contract: &DepositContract, address: &str) -> Cw1155DepositsByOwnerResponse {
+        app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Cw1155DepositsByOwner { address: address.to_string(), start_after: None, limit: None })
+            .unwrap()
+    }
+
+    #[test]
+    fn cw1155_single_and_batch_receives_are_tracked_per_token() {
+        let (mut app, deposit_id, _cw20_id, _cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw1155_contract = cw1155_instantiate(&mut app);
+        let hook_msg = Some(to_binary(&Cw1155HookMsg::Deposit {}).unwrap());
+
+        let msg = Cw1155ExecuteMsg::SendFrom { from: USER.to_string(), to: deposit_contract.addr().to_string(), token_id: "sword".to_string(), value: Uint128::new(40), msg: hook_msg.clone() };
+        app.execute_contract(Addr::unchecked(USER), cw1155_contract.clone(), &msg, &[]).unwrap();
+        let batch = vec![("sword".to_string(), Uint128::new(10)), ("shield".to_string(), Uint128::new(50))];
+        let msg = Cw1155ExecuteMsg::BatchSendFrom { from: USER.to_string(), to: deposit_contract.addr().to_string(), batch, msg: hook_msg.clone() };
+        app.execute_contract(Addr::unchecked(USER), cw1155_contract.clone(), &msg, &[]).unwrap();
+        //minted straight into the deposit contract, credited to the minter
+        let msg = Cw1155ExecuteMsg::Mint { to: deposit_contract.addr().to_string(), token_id: "potion".to_string(), value: Uint128::new(5), msg: hook_msg };
+        app.execute_contract(Addr::unchecked(USER), cw1155_contract.clone(), &msg, &[]).unwrap();
+
+        let deposits = get_cw1155_deposits_by_owner(&app, &deposit_contract, USER);
+        let mut held: Vec<(String, Uint128)> = deposits.deposits.iter().map(|((_, token_id), d)| (token_id.clone(), d.amount)).collect();
+        held.sort();
+        assert_eq!(held, vec![("potion".to_string(), Uint128::new(5)), ("shield".to_string(), Uint128::new(50)), ("sword".to_string(), Uint128::new(50))]);
+        assert_eq!(get_cw1155_balance(&app, &cw1155_contract, deposit_contract.addr().as_str(), "sword"), Uint128::new(50));
+
+        let page: Cw1155DepositResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Cw1155DepositsByContract { contract_addr: cw1155_contract.to_string(), start_after: None, limit: Some(2) })
+            .unwrap();
+        assert_eq!(page.deposits.len(), 2);
+        assert_eq!(page.next_start_after, Some(page.deposits[1].0.clone()));
+        let first_page = page.deposits;
+        let page: Cw1155DepositResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Cw1155DepositsByContract { contract_addr: cw1155_contract.to_string(), start_after: page.next_start_after, limit: Some(2) })
+            .unwrap();
+        assert_eq!(page.deposits.len(), 1);
+        assert!(!first_page.contains(&page.deposits[0]));
+        assert_eq!(page.next_start_after, None);
+
+        let sword = AssetInfo::Cw1155 { contract: cw1155_contract.to_string(), token_id: "sword".to_string() };
+        assert_eq!(get_asset_balance(&app, &deposit_contract, USER, &sword), Uint128::new(50));
+        assert_eq!(get_totals(&app, &deposit_contract, AssetKind::Cw1155).len(), 3);
+        assert_eq!(get_totals(&app, &deposit_contract, AssetKind::Cw1155)[2], (sword.key(), AssetTotal { amount: Uint128::new(50), depositors: 1 }));
+    }
+
+    #[test]
+    fn cw1155_balances_are_withdrawn_singly_or_in_batches() {
+        let (mut app, deposit_id, _cw20_id, _cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw1155_contract = cw1155_instantiate(&mut app);
+        let batch = vec![("sword".to_string(), Uint128::new(60)), ("shield".to_string(), Uint128::new(50))];
+        let msg = Cw1155ExecuteMsg::BatchSendFrom { from: USER.to_string(), to: deposit_contract.addr().to_string(), batch, msg: Some(to_binary(&Cw1155HookMsg::Deposit {}).unwrap()) };
+        app.execute_contract(Addr::unchecked(USER), cw1155_contract.clone(), &msg, &[]).unwrap();
+
+        let msg = ExecuteMsg::Withdraw1155 { contract_addr: cw1155_contract.to_string(), token_id: "sword".to_string(), amount: Uint128::new(20), recipient: None, msg: None };
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_cw1155_balance(&app, &cw1155_contract, USER, "sword"), Uint128::new(60));
+
+        let msg = ExecuteMsg::Withdraw1155 { contract_addr: cw1155_contract.to_string(), token_id: "sword".to_string(), amount: Uint128::new(20), recipient: None, msg: None };
+        let err = app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::UnknownCw1155Balance { owner: OTHER_USER.to_string(), contract: cw1155_contract.to_string(), token_id: "sword".to_string() }
+        );
+
+        let msg = ExecuteMsg::BatchWithdraw1155 { contract_addr: cw1155_contract.to_string(), batch: vec![], recipient: None, msg: None };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::EmptyBatch {});
+
+        //a batch is all or nothing
+        let batch = vec![("shield".to_string(), Uint128::new(50)), ("sword".to_string(), Uint128::new(41))];
+        let msg = ExecuteMsg::BatchWithdraw1155 { contract_addr: cw1155_contract.to_string(), batch, recipient: None, msg: None };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InsufficientBalance { asset: format!("{}/sword", cw1155_contract), available: Uint128::new(40), requested: Uint128::new(41) }
+        );
+        assert_eq!(get_cw1155_deposits_by_owner(&app, &deposit_contract, USER).deposits.len(), 2);
+
+        let batch = vec![("shield".to_string(), Uint128::new(50)), ("sword".to_string(), Uint128::new(40))];
+        let msg = ExecuteMsg::BatchWithdraw1155 { contract_addr: cw1155_contract.to_string(), batch, recipient: Some("recipient".to_string()), msg: None };
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_cw1155_balance(&app, &cw1155_contract, "recipient", "shield"), Uint128::new(50));
+        assert_eq!(get_cw1155_balance(&app, &cw1155_contract, "recipient", "sword"), Uint128::new(40));
+        assert!(get_cw1155_deposits_by_owner(&app, &deposit_contract, USER).deposits.is_empty());
+        assert!(get_totals(&app, &deposit_contract, AssetKind::Cw1155).iter().all(|(_, total)| total == &AssetTotal::default()));
+    }
+
+    #[test]
+    fn cw1155_deposits_move_through_the_asset_api() {
+        let (mut app, deposit_id, _cw20_id, _cw721_id) = store_code();
+        let msg = InstantiateMsg { admin: None, default_lock_duration: None, cw20_lock_durations: vec![], open: false, allowed_tokens: vec![] };
+        let deposit_contract = deposit_instantiate_with_msg(&mut app, deposit_id, msg);
+        let other_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw1155_contract = cw1155_instantiate(&mut app);
+        let hook_msg = Some(to_binary(&Cw1155HookMsg::Deposit {}).unwrap());
+
+        let send = Cw1155ExecuteMsg::SendFrom { from: USER.to_string(), to: deposit_contract.addr().to_string(), token_id: "sword".to_string(), value: Uint128::new(30), msg: hook_msg.clone() };
+        let err = app.execute_contract(Addr::unchecked(USER), cw1155_contract.clone(), &send, &[]).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::TokenNotAllowed { contract: cw1155_contract.to_string() });
+        let msg = ExecuteMsg::AddAllowedToken { contract: cw1155_contract.to_string(), kind: TokenKind::Cw1155 };
+        app.execute(Addr::unchecked(ADMIN), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        app.execute_contract(Addr::unchecked(USER), cw1155_contract.clone(), &send, &[]).unwrap();
+
+        let sword = AssetInfo::Cw1155 { contract: cw1155_contract.to_string(), token_id: "sword".to_string() };
+        let msg = ExecuteMsg::TransferDeposit { recipient: OTHER_USER.to_string(), asset: sword.clone(), amount: Uint128::new(10) };
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_asset_balance(&app, &deposit_contract, USER, &sword), Uint128::new(20));
+        assert_eq!(get_asset_balance(&app, &deposit_contract, OTHER_USER, &sword), Uint128::new(10));
+
+        //withdrawn with a msg the tokens land in the other deposit contract, still owned by the first
+        let msg = ExecuteMsg::Withdraw { asset: sword.clone(), amount: Uint128::new(20), recipient: Some(other_contract.addr().to_string()), msg: hook_msg };
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        let first = deposit_contract.addr().to_string();
+        assert_eq!(get_asset_balance(&app, &other_contract, &first, &sword), Uint128::new(20));
+        assert_eq!(get_cw1155_balance(&app, &cw1155_contract, other_contract.addr().as_str(), "sword"), Uint128::new(20));
+    }
+
+    fn get_offers(app: &App, deposit_contract: &DepositContract, cw721_contract: &NftContract, token_id: &str) -> Vec<(String, Offer)> {
+        let res: OffersResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Offers { contract_addr: cw721_contract.addr().to_string(), token_id: token_id.to_string(), start_after: None, limit: None })
+            .unwrap();
+        res.bids
+    }
+
+    #[test]
+    fn bids_on_escrowed_nfts_are_accepted_or_refunded() {
+        let (mut app, deposit_id, _cw20_id, cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw721_contract = cw721_instantiate(&mut app, cw721_id, "NFT".to_string(), "NFT".to_string(), USER.to_string());
+        let nft = cw721_contract.addr().to_string();
+        app.send_tokens(Addr::unchecked(USER), Addr::unchecked(OTHER_USER), &[coin(500, NATIVE_DENOM), coin(100, OTHER_DENOM)]).unwrap();
+
+        let msg = ExecuteMsg::PlaceBid { contract_addr: nft.clone(), token_id: "0".to_string() };
+        let err = app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg, vec![coin(100, NATIVE_DENOM)]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::NftNotDeposited { contract: nft.clone(), token_id: "0".to_string() });
+
+        mint_and_deposit_nft(&mut app, &deposit_contract, &cw721_contract, "0".to_string());
+        let msg = ExecuteMsg::PlaceBid { contract_addr: nft.clone(), token_id: "0".to_string() };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg.clone(), vec![coin(100, NATIVE_DENOM)]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::CannotBidOnOwnToken {});
+        let err = app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg.clone(), vec![coin(100, NATIVE_DENOM), coin(100, OTHER_DENOM)]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::InvalidCoin {});
+
+        //a second bid replaces the first, which is refunded
+        app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg.clone(), vec![coin(100, OTHER_DENOM)]).unwrap()).unwrap();
+        app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg.clone(), vec![coin(300, NATIVE_DENOM)]).unwrap()).unwrap();
+        assert_eq!(get_balance(&app, OTHER_USER.to_string(), OTHER_DENOM.to_string()).amount, Uint128::new(100));
+        assert_eq!(get_balance(&app, OTHER_USER.to_string(), NATIVE_DENOM.to_string()).amount, Uint128::new(200));
+        let err = app.execute(Addr::unchecked("bidder"), deposit_contract.call(msg.clone(), vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::InvalidCoin {});
+        app.send_tokens(Addr::unchecked(USER), Addr::unchecked("bidder"), &[coin(50, NATIVE_DENOM)]).unwrap();
+        app.execute(Addr::unchecked("bidder"), deposit_contract.call(msg, vec![coin(50, NATIVE_DENOM)]).unwrap()).unwrap();
+        assert_eq!(
+            get_offers(&app, &deposit_contract, &cw721_contract, "0"),
+            vec![
+                ("bidder".to_string(), Offer { token_id: "0".to_string(), amount: coin(50, NATIVE_DENOM) }),
+                (OTHER_USER.to_string(), Offer { token_id: "0".to_string(), amount: coin(300, NATIVE_DENOM) }),
+            ]
+        );
+        let page: OffersResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Offers { contract_addr: nft.clone(), token_id: "0".to_string(), start_after: Some("bidder".to_string()), limit: Some(1) })
+            .unwrap();
+        assert_eq!(page.bids.iter().map(|(bidder, _)| bidder.as_str()).collect::<Vec<_>>(), vec![OTHER_USER]);
+        assert_eq!(page.next_start_after, Some(OTHER_USER.to_string()));
+
+        let msg = ExecuteMsg::AcceptBid { contract_addr: nft.clone(), token_id: "0".to_string(), bidder: OTHER_USER.to_string() };
+        let err = app.execute(Addr::unchecked("bidder"), deposit_contract.call(msg.clone(), vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::InvalidOwner {});
+        let user_balance = get_balance(&app, USER.to_string(), NATIVE_DENOM.to_string()).amount;
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_balance(&app, USER.to_string(), NATIVE_DENOM.to_string()).amount, user_balance + Uint128::new(300));
+        let asset = AssetInfo::Cw721 { contract: nft.clone(), token_id: "0".to_string() };
+        assert_eq!(get_asset_balance(&app, &deposit_contract, OTHER_USER, &asset), Uint128::one());
+        assert_eq!(get_owner_of(&app, &cw721_contract, "0".to_string()).owner, deposit_contract.addr().to_string());
+
+        let msg = ExecuteMsg::CancelBid { contract_addr: nft.clone(), token_id: "0".to_string() };
+        let err = app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg.clone(), vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::NoBid { bidder: OTHER_USER.to_string() });
+        app.execute(Addr::unchecked("bidder"), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_balance(&app, "bidder".to_string(), NATIVE_DENOM.to_string()).amount, Uint128::new(50));
+        assert!(get_offers(&app, &deposit_contract, &cw721_contract, "0").is_empty());
+    }
+
+    fn get_listings(app: &App, deposit_contract: &DepositContract, collection: &str, start_after: Option<String>, limit: Option<u32>) -> ListingsResponse {
+        app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Listings { collection: collection.to_string(), start_after, limit })
+            .unwrap()
+    }
+
+    #[test]
+    fn listed_nfts_are_bought_with_native_or_cw20_payment() {
+        let (mut app, deposit_id, cw20_id, cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        let cw721_contract = cw721_instantiate(&mut app, cw721_id, "NFT".to_string(), "NFT".to_string(), USER.to_string());
+        let nft = cw721_contract.addr().to_string();
+        for token_id in ["0", "1", "2"] {
+            mint_and_deposit_nft(&mut app, &deposit_contract, &cw721_contract, token_id.to_string());
+        }
+
+        let fee = MarketplaceFee { rate: Decimal::percent(150), collector: "collector".to_string() };
+        let msg = ExecuteMsg::UpdateMarketplaceFee { marketplace_fee: Some(fee) };
+        let err = app.execute(Addr::unchecked(ADMIN), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::InvalidFee {});
+        let fee = MarketplaceFee { rate: Decimal::percent(10), collector: "collector".to_string() };
+        let msg = ExecuteMsg::UpdateMarketplaceFee { marketplace_fee: Some(fee.clone()) };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg.clone(), vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::Unauthorized {});
+        app.execute(Addr::unchecked(ADMIN), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        let config: ConfigResponse = app.wrap().query_wasm_smart(deposit_contract.addr(), &QueryMsg::Config {}).unwrap();
+        assert_eq!(config.marketplace_fee, Some(fee));
+
+        let native = AssetInfo::Native { denom: NATIVE_DENOM.to_string() };
+        let msg = ExecuteMsg::ListNft { contract_addr: nft.clone(), token_id: "0".to_string(), payment: native.clone(), price: Uint128::new(200) };
+        let err = app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg.clone(), vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::InvalidOwner {});
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        let msg = ExecuteMsg::ListNft { contract_addr: nft.clone(), token_id: "1".to_string(), payment: AssetInfo::Cw721 { contract: nft.clone(), token_id: "2".to_string() }, price: Uint128::one() };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::InvalidPaymentAsset {});
+        let cw20 = AssetInfo::Cw20 { contract: cw20_contract.addr().to_string() };
+        let msg = ExecuteMsg::ListNft { contract_addr: nft.clone(), token_id: "1".to_string(), payment: cw20.clone(), price: Uint128::new(1000) };
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        let msg = ExecuteMsg::ListNft { contract_addr: nft.clone(), token_id: "2".to_string(), payment: native.clone(), price: Uint128::new(10) };
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+
+        let page = get_listings(&app, &deposit_contract, &nft, None, Some(2));
+        assert_eq!(page.listings.iter().map(|(token_id, _)| token_id.as_str()).collect::<Vec<_>>(), vec!["0", "1"]);
+        assert_eq!(page.listings[1].1.seller, USER.to_string());
+        let page = get_listings(&app, &deposit_contract, &nft, page.next_start_after, Some(2));
+        assert_eq!(page.listings.len(), 1);
+        assert_eq!(page.next_start_after, None);
+
+        //only the seller delists
+        let msg = ExecuteMsg::DelistNft { contract_addr: nft.clone(), token_id: "2".to_string() };
+        let err = app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg.clone(), vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::InvalidOwner {});
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg.clone(), vec![]).unwrap()).unwrap();
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::ListingNotFound { contract: nft.clone(), token_id: "2".to_string() });
+
+        app.send_tokens(Addr::unchecked(USER), Addr::unchecked(OTHER_USER), &[coin(300, NATIVE_DENOM)]).unwrap();
+        let msg = ExecuteMsg::BuyNft { contract_addr: nft.clone(), token_id: "0".to_string() };
+        let err = app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg.clone(), vec![coin(150, NATIVE_DENOM)]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::IncorrectPayment { expected: format!("200{}", NATIVE_DENOM) });
+        let user_balance = get_balance(&app, USER.to_string(), NATIVE_DENOM.to_string()).amount;
+        app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg.clone(), vec![coin(200, NATIVE_DENOM)]).unwrap()).unwrap();
+        assert_eq!(get_balance(&app, USER.to_string(), NATIVE_DENOM.to_string()).amount, user_balance + Uint128::new(180));
+        assert_eq!(get_balance(&app, "collector".to_string(), NATIVE_DENOM.to_string()).amount, Uint128::new(20));
+        let token_0 = AssetInfo::Cw721 { contract: nft.clone(), token_id: "0".to_string() };
+        assert_eq!(get_asset_balance(&app, &deposit_contract, OTHER_USER, &token_0), Uint128::one());
+        assert_eq!(get_asset_balance(&app, &deposit_contract, USER, &token_0), Uint128::zero());
+        let err = app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg, vec![coin(100, NATIVE_DENOM)]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::ListingNotFound { contract: nft.clone(), token_id: "0".to_string() });
+
+        //cw20 listings are bought by sending the price along with a buy hook
+        let hook_msg = Cw20HookMsg::Buy { contract_addr: nft.clone(), token_id: "1".to_string() };
+        let msg = Cw20ExecuteMsg::Send { contract: deposit_contract.addr().to_string(), amount: Uint128::new(1000), msg: to_binary(&hook_msg).unwrap() };
+        app.execute(Addr::unchecked(USER), cw20_contract.call(Cw20ExecuteMsg::Transfer { recipient: "buyer".to_string(), amount: Uint128::new(1000) }).unwrap()).unwrap();
+        app.execute(Addr::unchecked("buyer"), cw20_contract.call(msg).unwrap()).unwrap();
+        assert_eq!(get_cw20_balance(&app, &cw20_contract, USER.to_string()).balance, Uint128::new(9900));
+        assert_eq!(get_cw20_balance(&app, &cw20_contract, "collector".to_string()).balance, Uint128::new(100));
+        let token_1 = AssetInfo::Cw721 { contract: nft.clone(), token_id: "1".to_string() };
+        assert_eq!(get_asset_balance(&app, &deposit_contract, "buyer", &token_1), Uint128::one());
+
+        //withdrawing a listed nft takes it off the market
+        let msg = ExecuteMsg::ListNft { contract_addr: nft.clone(), token_id: "1".to_string(), payment: native, price: Uint128::new(10) };
+        app.execute(Addr::unchecked("buyer"), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_listings(&app, &deposit_contract, &nft, None, None).listings.len(), 1);
+        let msg = ExecuteMsg::Withdraw { asset: token_1, amount: Uint128::one(), recipient: None, msg: None };
+        app.execute(Addr::unchecked("buyer"), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_owner_of(&app, &cw721_contract, "1".to_string()).owner, "buyer".to_string());
+        assert!(get_listings(&app, &deposit_contract, &nft, None, None).listings.is_empty());
+    }
+
+    fn get_auction(app: &App, deposit_contract: &DepositContract, cw721_contract: &NftContract, token_id: &str) -> AuctionResponse {
+        app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Auction { contract_addr: cw721_contract.addr().to_string(), token_id: token_id.to_string() })
+            .unwrap()
+    }
+
+    fn get_auctions(app: &App, deposit_contract: &DepositContract, collection: &str, start_after: Option<String>, limit: Option<u32>) -> AuctionsResponse {
+        app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::Auctions { collection: collection.to_string(), start_after, limit })
+            .unwrap()
+    }
+
+    #[test]
+    fn english_auctions_refund_outbid_funds_and_extend_late_bids() {
+        let (mut app, deposit_id, _cw20_id, cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw721_contract = cw721_instantiate(&mut app, cw721_id, "NFT".to_string(), "NFT".to_string(), USER.to_string());
+        let nft = cw721_contract.addr().to_string();
+        mint_and_deposit_nft(&mut app, &deposit_contract, &cw721_contract, "0".to_string());
+        app.send_tokens(Addr::unchecked(USER), Addr::unchecked(OTHER_USER), &[coin(300, NATIVE_DENOM), coin(100, OTHER_DENOM)]).unwrap();
+        app.send_tokens(Addr::unchecked(USER), Addr::unchecked("bidder"), &[coin(300, NATIVE_DENOM)]).unwrap();
+
+        let native = AssetInfo::Native { denom: NATIVE_DENOM.to_string() };
+        let kind = AuctionKind::English { min_increment: Uint128::new(10), extension: Duration::Time(30) };
+        let msg = ExecuteMsg::StartAuction { contract_addr: nft.clone(), token_id: "0".to_string(), payment: native.clone(), kind, start_price: Uint128::new(100), duration: Duration::Height(10) };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::InvalidAuctionDuration {});
+        let kind = AuctionKind::English { min_increment: Uint128::new(10), extension: Duration::Height(3) };
+        let msg = ExecuteMsg::StartAuction { contract_addr: nft.clone(), token_id: "0".to_string(), payment: native.clone(), kind, start_price: Uint128::new(100), duration: Duration::Height(10) };
+        let err = app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg.clone(), vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::InvalidOwner {});
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg.clone(), vec![]).unwrap()).unwrap();
+        let in_auction = ContractError::NftInAuction { contract: nft.clone(), token_id: "0".to_string() };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), in_auction);
+        let start_height = app.block_info().height;
+        assert_eq!(get_auction(&app, &deposit_contract, &cw721_contract, "0").auction.end, Expiration::AtHeight(start_height + 10));
+
+        //the seller can't take the nft out while it is auctioned
+        let token_0 = AssetInfo::Cw721 { contract: nft.clone(), token_id: "0".to_string() };
+        let msg = ExecuteMsg::Withdraw { asset: token_0.clone(), amount: Uint128::one(), recipient: None, msg: None };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), in_auction);
+        let msg = ExecuteMsg::ListNft { contract_addr: nft.clone(), token_id: "0".to_string(), payment: native, price: Uint128::new(10) };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), in_auction);
+
+        let msg = ExecuteMsg::BidAuction { contract_addr: nft.clone(), token_id: "0".to_string() };
+        let err = app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg.clone(), vec![coin(50, NATIVE_DENOM)]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::BidTooLow { min: Uint128::new(100) });
+        let err = app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg.clone(), vec![coin(100, OTHER_DENOM)]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::IncorrectPayment { expected: NATIVE_DENOM.to_string() });
+        app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg.clone(), vec![coin(100, NATIVE_DENOM)]).unwrap()).unwrap();
+        let err = app.execute(Addr::unchecked("bidder"), deposit_contract.call(msg.clone(), vec![coin(105, NATIVE_DENOM)]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::BidTooLow { min: Uint128::new(110) });
+        app.execute(Addr::unchecked("bidder"), deposit_contract.call(msg.clone(), vec![coin(150, NATIVE_DENOM)]).unwrap()).unwrap();
+        assert_eq!(get_balance(&app, OTHER_USER.to_string(), NATIVE_DENOM.to_string()).amount, Uint128::new(300));
+        let cancel = ExecuteMsg::CancelAuction { contract_addr: nft.clone(), token_id: "0".to_string() };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(cancel, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::AuctionHasBids {});
+
+        //a bid in the last blocks pushes the end out
+        app.update_block(|block| block.height += 8);
+        app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(msg.clone(), vec![coin(160, NATIVE_DENOM)]).unwrap()).unwrap();
+        assert_eq!(get_balance(&app, "bidder".to_string(), NATIVE_DENOM.to_string()).amount, Uint128::new(300));
+        let auction = get_auction(&app, &deposit_contract, &cw721_contract, "0");
+        assert_eq!(auction.auction.end, Expiration::AtHeight(start_height + 11));
+        assert_eq!(auction.min_bid, Uint128::new(170));
+
+        let settle = ExecuteMsg::SettleAuction { contract_addr: nft.clone(), token_id: "0".to_string() };
+        app.update_block(|block| block.height += 2);
+        let err = app.execute(Addr::unchecked("bidder"), deposit_contract.call(settle.clone(), vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::AuctionNotEnded {});
+        app.update_block(|block| block.height += 1);
+        let err = app.execute(Addr::unchecked("bidder"), deposit_contract.call(msg, vec![coin(200, NATIVE_DENOM)]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::AuctionEnded {});
+        let user_balance = get_balance(&app, USER.to_string(), NATIVE_DENOM.to_string()).amount;
+        app.execute(Addr::unchecked("bidder"), deposit_contract.call(settle, vec![]).unwrap()).unwrap();
+        assert_eq!(get_balance(&app, USER.to_string(), NATIVE_DENOM.to_string()).amount, user_balance + Uint128::new(160));
+        assert_eq!(get_asset_balance(&app, &deposit_contract, OTHER_USER, &token_0), Uint128::one());
+        assert!(get_auctions(&app, &deposit_contract, &nft, None, None).auctions.is_empty());
+
+        //the history outlives the auction
+        let res: AuctionBidsResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::AuctionBids { auction_id: 1, start_after: None, limit: Some(2) })
+            .unwrap();
+        assert_eq!(res.bids.iter().map(|(_, bid)| (bid.bidder.as_str(), bid.amount.u128())).collect::<Vec<_>>(), vec![(OTHER_USER, 100), ("bidder", 150)]);
+        assert_eq!(res.next_start_after, Some(2));
+        let res: AuctionBidsResponse = app.wrap()
+            .query_wasm_smart(deposit_contract.addr(), &QueryMsg::AuctionBids { auction_id: 1, start_after: res.next_start_after, limit: Some(2) })
+            .unwrap();
+        assert_eq!(res.bids, vec![(3, AuctionBid { bidder: OTHER_USER.to_string(), amount: Uint128::new(160), height: start_height + 8 })]);
+    }
+
+    #[test]
+    fn dutch_auctions_sell_to_the_first_bid_at_the_falling_price() {
+        let (mut app, deposit_id, cw20_id, cw721_id) = store_code();
+        let deposit_contract = deposit_instantiate(&mut app, deposit_id);
+        let cw20_contract = cw_20_instantiate(&mut app, cw20_id);
+        let cw721_contract = cw721_instantiate(&mut app, cw721_id, "NFT".to_string(), "NFT".to_string(), USER.to_string());
+        let nft = cw721_contract.addr().to_string();
+        mint_and_deposit_nft(&mut app, &deposit_contract, &cw721_contract, "0".to_string());
+        mint_and_deposit_nft(&mut app, &deposit_contract, &cw721_contract, "1".to_string());
+        let fee = MarketplaceFee { rate: Decimal::percent(10), collector: "collector".to_string() };
+        let msg = ExecuteMsg::UpdateMarketplaceFee { marketplace_fee: Some(fee) };
+        app.execute(Addr::unchecked(ADMIN), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+
+        let cw20 = AssetInfo::Cw20 { contract: cw20_contract.addr().to_string() };
+        let msg = ExecuteMsg::StartAuction { contract_addr: nft.clone(), token_id: "0".to_string(), payment: cw20.clone(), kind: AuctionKind::Dutch { end_price: Uint128::new(1000) }, start_price: Uint128::new(1000), duration: Duration::Time(100) };
+        let err = app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::InvalidDutchPrices {});
+        for token_id in ["0", "1"] {
+            let msg = ExecuteMsg::StartAuction { contract_addr: nft.clone(), token_id: token_id.to_string(), payment: cw20.clone(), kind: AuctionKind::Dutch { end_price: Uint128::new(200) }, start_price: Uint128::new(1000), duration: Duration::Time(100) };
+            app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        }
+        let page = get_auctions(&app, &deposit_contract, &nft, None, Some(1));
+        assert_eq!(page.auctions[0].0, "0".to_string());
+        let page = get_auctions(&app, &deposit_contract, &nft, page.next_start_after, Some(1));
+        assert_eq!(page.auctions[0].1.id, 2);
+
+        assert_eq!(get_auction(&app, &deposit_contract, &cw721_contract, "0").min_bid, Uint128::new(1000));
+        app.update_block(|block| block.time = block.time.plus_seconds(50));
+        assert_eq!(get_auction(&app, &deposit_contract, &cw721_contract, "0").min_bid, Uint128::new(600));
+
+        //paying over the price returns the difference
+        app.execute(Addr::unchecked(USER), cw20_contract.call(Cw20ExecuteMsg::Transfer { recipient: "buyer".to_string(), amount: Uint128::new(700) }).unwrap()).unwrap();
+        let hook_msg = Cw20HookMsg::BidAuction { contract_addr: nft.clone(), token_id: "0".to_string() };
+        let msg = Cw20ExecuteMsg::Send { contract: deposit_contract.addr().to_string(), amount: Uint128::new(500), msg: to_binary(&hook_msg).unwrap() };
+        let err = app.execute(Addr::unchecked("buyer"), cw20_contract.call(msg).unwrap()).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::BidTooLow { min: Uint128::new(600) });
+        let msg = Cw20ExecuteMsg::Send { contract: deposit_contract.addr().to_string(), amount: Uint128::new(700), msg: to_binary(&hook_msg).unwrap() };
+        app.execute(Addr::unchecked("buyer"), cw20_contract.call(msg).unwrap()).unwrap();
+        assert_eq!(get_cw20_balance(&app, &cw20_contract, "buyer".to_string()).balance, Uint128::new(100));
+        assert_eq!(get_cw20_balance(&app, &cw20_contract, USER.to_string()).balance, Uint128::new(9300 + 540));
+        assert_eq!(get_cw20_balance(&app, &cw20_contract, "collector".to_string()).balance, Uint128::new(60));
+        let token_0 = AssetInfo::Cw721 { contract: nft.clone(), token_id: "0".to_string() };
+        assert_eq!(get_asset_balance(&app, &deposit_contract, "buyer", &token_0), Uint128::one());
+
+        //an auction without bids gives the nft back to the seller once settled
+        app.update_block(|block| block.time = block.time.plus_seconds(50));
+        let settle = ExecuteMsg::SettleAuction { contract_addr: nft.clone(), token_id: "1".to_string() };
+        app.execute(Addr::unchecked(OTHER_USER), deposit_contract.call(settle, vec![]).unwrap()).unwrap();
+        assert!(get_auctions(&app, &deposit_contract, &nft, None, None).auctions.is_empty());
+        let token_1 = AssetInfo::Cw721 { contract: nft.clone(), token_id: "1".to_string() };
+        let msg = ExecuteMsg::Withdraw { asset: token_1, amount: Uint128::one(), recipient: None, msg: None };
+        app.execute(Addr::unchecked(USER), deposit_contract.call(msg, vec![]).unwrap()).unwrap();
+        assert_eq!(get_owner_of(&app, &cw721_contract, "1".to_string()).owner, USER.to_string());
+    }
 }
@@ -4,6 +4,7 @@ mod error;
 pub mod msg;
 pub mod state;
 pub mod helpers;
+mod migrations;
 pub mod integration_tests;
 
 pub use crate::error::ContractError;
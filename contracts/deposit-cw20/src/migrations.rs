@@ -0,0 +1,271 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use cosmwasm_std::{Addr, Coin, CustomMsg, DepsMut, Empty, Env, Order, StdResult, Uint128};
+use cw_storage_plus::{KeyDeserialize, Map};
+use cw_utils::Expiration;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::contract::DEFAULT_LOCK_DURATION;
+use crate::error::ContractError;
+use crate::msg::MigrateMsg;
+use crate::state::{AssetTotal, Config, Cw20Deposits, Cw20Lot, Cw721Deposits, Deposit, Deposits};
+
+//Deposits as stored by v0.1.0, count was signed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+struct DepositsV0_1 {
+    pub count: i32,
+    pub owner: Addr,
+    pub coins: Coin,
+}
+
+//Cw20Lot as stored before v0.5.0, without its lock duration
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+struct Cw20LotV0_4 {
+    pub amount: Uint128,
+    pub stake_time: Expiration,
+}
+
+//v0.1.0 -> v0.2.0
+//widens Deposits::count to u64, creates the Config v0.1.0 never stored
+//and turns every cw20 position into a single lot.
+pub fn v0_2_0<C>(
+    deps: DepsMut,
+    env: &Env,
+    contract: &Deposit<C>,
+    msg: &MigrateMsg,
+) -> Result<(), ContractError>
+where
+    C: CustomMsg,
+{
+    let legacy_deposits: Map<(&str, &str), DepositsV0_1> = Map::new("deposits");
+    let deposits: Vec<((String, String), DepositsV0_1)> = legacy_deposits
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for ((owner, denom), deposit) in deposits {
+        let migrated = Deposits {
+            count: deposit.count.max(0) as u64,
+            owner: deposit.owner,
+            coins: deposit.coins,
+        };
+        //the snapshot save reads the old entry back as a u64 count, which a
+        //negative v0.1.0 count can't parse into, so drop it first
+        legacy_deposits.remove(deps.storage, (&owner, &denom));
+        contract
+            .deposits
+            .save(deps.storage, (&owner, &denom), &migrated, env.block.height)?;
+    }
+
+    let admin = match &msg.admin {
+        Some(admin) => deps.api.addr_validate(admin)?,
+        None => {
+            return Err(ContractError::MigrationOptionRequired {
+                option: "admin".to_string(),
+            })
+        }
+    };
+    let config = Config {
+        admin,
+        pending_admin: None,
+        default_lock_duration: msg.default_lock_duration.unwrap_or(DEFAULT_LOCK_DURATION),
+        //v0.1.0 accepted every token
+        open: msg.open.unwrap_or(true),
+        early_withdraw: None,
+        unbonding_period: None,
+        marketplace_fee: None,
+    };
+    contract.config.save(deps.storage, &config)?;
+
+    let positions: Vec<((String, String), Cw20Deposits)> = contract
+        .cw20_deposits
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    let mut lot_id = contract
+        .cw20_lot_seq
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    for ((owner, cw20_contract), position) in positions {
+        if position.amount.is_zero() {
+            continue;
+        }
+        lot_id += 1;
+        contract.cw20_lots.save(
+            deps.storage,
+            (&owner, &cw20_contract, lot_id),
+            &Cw20Lot {
+                amount: position.amount,
+                stake_time: position.stake_time,
+                //v0.1.0 had no per token locks
+                lock_duration: config.default_lock_duration,
+            },
+        )?;
+    }
+    contract.cw20_lot_seq.save(deps.storage, &lot_id)?;
+
+    Ok(())
+}
+
+//v0.2.0 -> v0.3.0
+//native and cw20 deposits became snapshot maps in the same namespaces.
+//existing entries have no changelog yet, so each one is written back at
+//the upgrade height, which makes it visible to the at-height queries.
+//heights before the upgrade resolve to the balance carried over.
+pub fn v0_3_0<C>(deps: DepsMut, env: &Env, contract: &Deposit<C>) -> Result<(), ContractError>
+where
+    C: CustomMsg,
+{
+    let deposits: Vec<((String, String), Deposits)> = contract
+        .deposits
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for ((owner, denom), deposit) in deposits {
+        contract
+            .deposits
+            .save(deps.storage, (&owner, &denom), &deposit, env.block.height)?;
+    }
+
+    let positions: Vec<((String, String), Cw20Deposits)> = contract
+        .cw20_deposits
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for ((owner, cw20_contract), position) in positions {
+        contract.cw20_deposits.save(
+            deps.storage,
+            (&owner, &cw20_contract),
+            &position,
+            env.block.height,
+        )?;
+    }
+
+    Ok(())
+}
+
+//v0.3.0 -> v0.4.0
+//builds the per-asset totals from the positions already held.
+pub fn v0_4_0<C>(deps: DepsMut, contract: &Deposit<C>) -> Result<(), ContractError>
+where
+    C: CustomMsg,
+{
+    let mut native: BTreeMap<String, AssetTotal> = BTreeMap::new();
+    let deposits: Vec<((String, String), Deposits)> = contract
+        .deposits
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for ((_, denom), deposit) in deposits {
+        add_to_total(native.entry(denom).or_default(), deposit.coins.amount)?;
+    }
+    for (denom, total) in native {
+        contract.native_totals.save(deps.storage, &denom, &total)?;
+    }
+
+    let mut cw20: BTreeMap<String, AssetTotal> = BTreeMap::new();
+    let positions: Vec<((String, String), Cw20Deposits)> = contract
+        .cw20_deposits
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for ((_, cw20_contract), position) in positions {
+        add_to_total(cw20.entry(cw20_contract).or_default(), position.amount)?;
+    }
+    for (cw20_contract, total) in cw20 {
+        contract
+            .cw20_totals
+            .save(deps.storage, &cw20_contract, &total)?;
+    }
+
+    let mut cw721: BTreeMap<String, (AssetTotal, BTreeSet<String>)> = BTreeMap::new();
+    let nfts: Vec<((String, String), Cw721Deposits)> = contract
+        .cw721_deposits
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for ((collection, _), nft) in nfts {
+        let (total, owners) = cw721.entry(collection).or_default();
+        total.amount += Uint128::one();
+        if owners.insert(nft.owner) {
+            total.depositors += 1;
+        }
+    }
+    for (collection, (total, _)) in cw721 {
+        contract
+            .cw721_totals
+            .save(deps.storage, &collection, &total)?;
+    }
+
+    Ok(())
+}
+
+//v0.4.0 -> v0.5.0
+//lots now record the lock they were deposited with. older lots get the
+//lock currently configured for their cw20, which is what they were
+//given unless it changed since.
+pub fn v0_5_0<C>(deps: DepsMut, contract: &Deposit<C>) -> Result<(), ContractError>
+where
+    C: CustomMsg,
+{
+    let legacy_lots: Map<(&str, &str, u64), Cw20LotV0_4> = Map::new("cw20_lots");
+    let lots: Vec<(Vec<u8>, Cw20LotV0_4)> = legacy_lots
+        .range_raw(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    if lots.is_empty() {
+        return Ok(());
+    }
+    let default_lock_duration = contract.config.load(deps.storage)?.default_lock_duration;
+    for (raw_key, lot) in lots {
+        let (owner, cw20_contract, lot_id) = <(String, String, u64)>::from_vec(raw_key)?;
+        let lock_duration = contract
+            .cw20_lock_durations
+            .may_load(deps.storage, &cw20_contract)?
+            .unwrap_or(default_lock_duration);
+        contract.cw20_lots.save(
+            deps.storage,
+            (&owner, &cw20_contract, lot_id),
+            &Cw20Lot {
+                amount: lot.amount,
+                stake_time: lot.stake_time,
+                lock_duration,
+            },
+        )?;
+    }
+
+    Ok(())
+}
+
+//v0.5.0 -> v0.6.0
+//indexes every nft custody by owner. past owners come from the changelog,
+//which records who held a token before each change.
+pub fn v0_6_0<C>(deps: DepsMut, contract: &Deposit<C>) -> Result<(), ContractError>
+where
+    C: CustomMsg,
+{
+    let mut custody: BTreeSet<(String, String, String)> = BTreeSet::new();
+    let nfts: Vec<((String, String), Cw721Deposits)> = contract
+        .cw721_deposits
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for ((collection, token_id), nft) in nfts {
+        custody.insert((nft.owner, collection, token_id));
+    }
+    let previous: Vec<Option<Cw721Deposits>> = contract
+        .cw721_deposits
+        .changelog()
+        .range_raw(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, change)| change.old))
+        .collect::<StdResult<_>>()?;
+    for nft in previous.into_iter().flatten() {
+        custody.insert((nft.owner, nft.contract, nft.token_id));
+    }
+    for (owner, collection, token_id) in custody {
+        contract
+            .cw721_custody
+            .save(deps.storage, (&owner, &collection, &token_id), &Empty {})?;
+    }
+
+    Ok(())
+}
+
+fn add_to_total(total: &mut AssetTotal, amount: Uint128) -> StdResult<()> {
+    if !amount.is_zero() {
+        total.amount = total.amount.checked_add(amount)?;
+        total.depositors += 1;
+    }
+    Ok(())
+}
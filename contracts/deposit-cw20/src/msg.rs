@@ -1,67 +1,430 @@
-use cosmwasm_std::Uint128;
+use cosmwasm_std::{Binary, Uint128};
 use cw721::Cw721ReceiveMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use cw20::Cw20ReceiveMsg;
+use cw1155::{Cw1155BatchReceiveMsg, Cw1155ReceiveMsg};
+use cw_utils::{Duration, Expiration};
 
-use crate::state::{Cw20Deposits, Deposits, Cw721Deposits};
+use crate::state::{Allowance, AssetInfo, AssetTotal, Cw20Claim, EarlyWithdraw, RewardPool, RewardToken, Cw20Deposits, Cw20Lot, Deposits, Cw721Deposits, Cw1155Deposits, Auction, AuctionBid, AuctionKind, Listing, MarketplaceFee, Offer, TokenKind};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct InstantiateMsg {
+    //defaults to the instantiating address
+    pub admin: Option<String>,
+    //lock applied to cw20 deposits without an override, defaults to 20 blocks
+    pub default_lock_duration: Option<Duration>,
+    #[serde(default)]
+    pub cw20_lock_durations: Vec<Cw20LockDuration>,
+    //accept any cw20, cw721 or cw1155 instead of only allowed_tokens, defaults to open like
+    //contracts instantiated with {} before the allowlist existed
+    #[serde(default = "default_open")]
+    pub open: bool,
+    #[serde(default)]
+    pub allowed_tokens: Vec<AllowedToken>,
+}
+
+fn default_open() -> bool {
+    true
+}
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AllowedToken {
+    pub contract: String,
+    pub kind: TokenKind,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Cw20LockDuration {
+    pub contract: String,
+    pub duration: Duration,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     Deposit { },
-    Withdraw { amount:u128, denom:String },
-    Receive(Cw20ReceiveMsg),
+    //withdraws any deposited asset, a cw721 by its depositor or one of their operators with an amount of one.
+    //recipient defaults to the sender. With msg the asset is sent into the recipient contract,
+    //cw20 through Send and cw721 through SendNft. Natives are always sent as is and reject a msg
+    Withdraw { asset: AssetInfo, amount: Uint128, recipient: Option<String>, msg: Option<Binary> },
+    //cw20 and cw1155 both call receive, told apart by the payload. There is no separate
+    //Receive1155, cw1155 deposits come through receive as ReceiveMsg::Cw1155 and through
+    //BatchReceive, the names cw1155-base calls receivers with
+    Receive(ReceiveMsg),
     ReceiveNft(Cw721ReceiveMsg),
-    WithdrawCw20 { address: String, amount:Uint128 },
-    WithdrawNft { contract_addr: String, token_id: String },
+    BatchReceive(Cw1155BatchReceiveMsg),
+    //withdraw of a single cw1155 balance, with msg it is sent into the recipient contract
+    Withdraw1155 { contract_addr: String, token_id: String, amount: Uint128, recipient: Option<String>, msg: Option<Binary> },
+    //withdraws several token_ids of one cw1155 contract in a single BatchSendFrom
+    BatchWithdraw1155 { contract_addr: String, batch: Vec<(String, Uint128)>, recipient: Option<String>, msg: Option<Binary> },
+    //withdraws cw20 lots before they unlock, minus the early withdraw penalty.
+    //with an unbonding period set the rest is queued as a claim instead of sent
+    EmergencyWithdrawCw20 { address: String, amount:Uint128 },
+    //moves deposited balance to recipient inside the ledger, for cw20 only unlocked lots and a cw721 with its escrow
+    TransferDeposit { recipient: String, asset: AssetInfo, amount: Uint128 },
+    //lets spender withdraw amount more of the sender's asset until expires, never expires by default
+    IncreaseAllowance { spender: String, asset: AssetInfo, amount: Uint128, expires: Option<Expiration> },
+    DecreaseAllowance { spender: String, asset: AssetInfo, amount: Uint128, expires: Option<Expiration> },
+    //withdraws from owner's deposit to recipient within the sender's allowance
+    WithdrawFrom { owner: String, asset: AssetInfo, amount: Uint128, recipient: String },
+    //takes unlocked cw20 out of the sender's position into a claim released after the unbonding period
+    UnbondCw20 { address: String, amount:Uint128 },
+    //pays out every claim of the sender that has been released
+    ClaimUnbonded {},
+    //allows operator to withdraw any of the sender's escrowed nfts
+    ApproveNftOperator { operator: String, expires: Option<Expiration> },
+    RevokeNftOperator { operator: String },
+    UpdateConfig { default_lock_duration: Option<Duration>, open: Option<bool> },
+    //None disables EmergencyWithdrawCw20
+    UpdateEarlyWithdraw { early_withdraw: Option<EarlyWithdraw> },
+    //None lets Withdraw pay out cw20 directly again
+    UpdateUnbondingPeriod { unbonding_period: Option<Duration> },
+    //None stops charging a fee on nft sales
+    UpdateMarketplaceFee { marketplace_fee: Option<MarketplaceFee> },
+    //first step of an admin transfer, the new admin has to accept
+    ProposeNewAdmin { new_admin: String },
+    AcceptAdmin {},
+    AddAllowedToken { contract: String, kind: TokenKind },
+    RemoveAllowedToken { contract: String },
+    //sets the lock for a single cw20, None falls back to the default
+    UpdateCw20LockDuration { contract: String, duration: Option<Duration> },
+    //creates a reward pool for stakers of staked_token or changes its emission rate
+    SetRewardPool { staked_token: String, reward: RewardToken, reward_per_block: Uint128 },
+    //adds the attached coins to the native reward pools of staked_token
+    FundRewards { staked_token: String },
+    ClaimRewards { staked_token: String },
+    //creates a reward pool paying reward_per_block to every escrowed nft of collection, or changes its rate
+    SetNftRewardPool { collection: String, reward: RewardToken, reward_per_block: Uint128 },
+    //adds the attached coins to the native reward pools of collection
+    FundNftRewards { collection: String },
+    //claims the rewards of every nft the sender has in escrow
+    ClaimNftRewards {},
+    //bids the single attached coin on a deposited nft, replacing and refunding an earlier bid of the sender
+    PlaceBid { contract_addr: String, token_id: String },
+    //refunds the sender's bid
+    CancelBid { contract_addr: String, token_id: String },
+    //hands the nft to bidder inside the ledger and pays the bid out to the depositor
+    AcceptBid { contract_addr: String, token_id: String, bidder: String },
+    //offers a deposited nft for price of payment, a native denom or an allowed cw20. Listing again replaces the price
+    ListNft { contract_addr: String, token_id: String, payment: AssetInfo, price: Uint128 },
+    DelistNft { contract_addr: String, token_id: String },
+    //buys a listing priced in a native denom with exactly the price attached, cw20 listings are bought through Cw20HookMsg::Buy
+    BuyNft { contract_addr: String, token_id: String },
+    //auctions a deposited nft for payment, a native denom or an allowed cw20, ending duration from now
+    StartAuction { contract_addr: String, token_id: String, payment: AssetInfo, kind: AuctionKind, start_price: Uint128, duration: Duration },
+    //only before the first bid
+    CancelAuction { contract_addr: String, token_id: String },
+    //bids with the native coin attached, cw20 auctions are bid on through Cw20HookMsg::BidAuction
+    BidAuction { contract_addr: String, token_id: String },
+    //anyone can settle an ended auction, the nft goes to the highest bidder and the bid to the seller
+    SettleAuction { contract_addr: String, token_id: String },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    Deposits { address: String },
-    Cw20Deposits { address: String },
-    Cw721DepositsByContract {contract_addr: String },
-    Cw721DepositsByOwner { address: String },
+    Deposits { address: String, start_after: Option<String>, limit: Option<u32> },
+    Cw20Deposits { address: String, start_after: Option<String>, limit: Option<u32> },
+    //native deposits of address as of the start of height. A page looks at limit of the denoms
+    //address ever deposited, so it can come back short with more to follow
+    DepositsAt { address: String, height: u64, start_after: Option<String>, limit: Option<u32> },
+    //cw20 deposits of address as of the start of height, paged like DepositsAt
+    Cw20DepositsAt { address: String, height: u64, start_after: Option<String>, limit: Option<u32> },
+    //lots of a single cw20 position. total is the whole position, locked and unlocked split the returned lots
+    Cw20Lots { address: String, contract: String, start_after: Option<u64>, limit: Option<u32> },
+    //every allowance owner has given, as (spender, allowance). start_after is (spender, asset key)
+    Allowances { owner: String, start_after: Option<(String, String)>, limit: Option<u32> },
+    //deposited amount of asset held for address, one or zero for a cw721
+    Balance { address: String, asset: AssetInfo },
+    //unbonding cw20 of address, released or not. start_after is a claim id
+    Claims { address: String, start_after: Option<u64>, limit: Option<u32> },
+    Cw721DepositsByContract {contract_addr: String, start_after: Option<String>, limit: Option<u32> },
+    //start_after is (contract_addr, token_id)
+    Cw721DepositsByOwner { address: String, start_after: Option<(String, String)>, limit: Option<u32> },
+    //custody history of a single escrowed nft, as (height, previous record)
+    Cw721DepositChangelog { contract_addr: String, token_id: String, start_after: Option<u64>, limit: Option<u32> },
+    //custody record of an nft as of the start of height
+    Cw721DepositAt { contract_addr: String, token_id: String, height: u64 },
+    //nfts escrowed by address as of the start of height, start_after is (contract_addr, token_id).
+    //a page looks at limit of the nfts address ever held, so it can come back short with more to follow
+    Cw721DepositsByOwnerAt { address: String, height: u64, start_after: Option<(String, String)>, limit: Option<u32> },
+    Cw721Operators { address: String, start_after: Option<String>, limit: Option<u32> },
+    //start_after is (token_id, owner)
+    Cw1155DepositsByContract { contract_addr: String, start_after: Option<(String, String)>, limit: Option<u32> },
+    //start_after is (contract_addr, token_id)
+    Cw1155DepositsByOwner { address: String, start_after: Option<(String, String)>, limit: Option<u32> },
+    LockSchedule { start_after: Option<String>, limit: Option<u32> },
+    //number of open cw20 deposits, as of the start of height when given
+    TotalCw20Deposits { height: Option<u64> },
+    //(height, total before that height's changes)
+    TotalCw20DepositsChangelog { start_after: Option<u64>, limit: Option<u32> },
+    Config {},
+    //running totals and depositor counts of the assets of kind held, start_after is a denom,
+    //contract or for cw1155 contract/token_id
+    Totals { kind: AssetKind, start_after: Option<String>, limit: Option<u32> },
+    //unclaimed rewards of address from every pool of staked_token
+    PendingRewards { address: String, staked_token: String },
+    //start_after is a reward denom or cw20 contract
+    RewardPools { staked_token: String, start_after: Option<String>, limit: Option<u32> },
+    //unclaimed rewards of every nft owner has in escrow
+    PendingNftRewards { owner: String },
+    //start_after is a reward denom or cw20 contract
+    NftRewardPools { collection: String, start_after: Option<String>, limit: Option<u32> },
+    AllowedTokens { start_after: Option<String>, limit: Option<u32> },
+    //open bids on a deposited nft, as (bidder, offer). start_after is a bidder
+    Offers { contract_addr: String, token_id: String, start_after: Option<String>, limit: Option<u32> },
+    //start_after is a token_id
+    Listings { collection: String, start_after: Option<String>, limit: Option<u32> },
+    Auction { contract_addr: String, token_id: String },
+    //auctions not settled or cancelled yet, start_after is a token_id
+    Auctions { collection: String, start_after: Option<String>, limit: Option<u32> },
+    //every bid placed in the auction, settled ones included
+    AuctionBids { auction_id: u64, start_after: Option<u64>, limit: Option<u32> },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct DepositResponse {
     pub deposits: Vec<(String, Deposits)>,
+    pub next_start_after: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct Cw20DepositResponse {
     pub deposits: Vec<(String, Cw20Deposits)>,
+    pub next_start_after: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Cw20LotsResponse {
+    pub lots: Vec<(u64, Cw20Lot)>,
+    pub total: Uint128,
+    pub locked: Uint128,
+    pub unlocked: Uint128,
+    pub next_start_after: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BalanceResponse {
+    pub asset: AssetInfo,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AllowancesResponse {
+    pub allowances: Vec<(String, Allowance)>,
+    pub next_start_after: Option<(String, String)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ClaimsResponse {
+    pub claims: Vec<(u64, Cw20Claim)>,
+    pub next_start_after: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TotalCw20DepositsResponse {
+    pub total: u64,
+    pub height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TotalCw20DepositsChangelogResponse {
+    pub changelog: Vec<(u64, Option<u64>)>,
+    pub next_start_after: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct Cw721DepositResponse {
     pub deposits: Vec<(String, Cw721Deposits)>,
+    pub next_start_after: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Cw721DepositsByOwnerResponse {
+    pub deposits: Vec<((String, String), Cw721Deposits)>,
+    pub next_start_after: Option<(String, String)>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub enum MigrateMsg {}
+pub struct Cw721DepositAtResponse {
+    pub deposit: Option<Cw721Deposits>,
+    pub height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Cw721DepositChangelogResponse {
+    pub changelog: Vec<(u64, Option<Cw721Deposits>)>,
+    pub next_start_after: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Cw721OperatorsResponse {
+    pub operators: Vec<(String, Expiration)>,
+    pub next_start_after: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Cw1155DepositResponse {
+    pub deposits: Vec<((String, String), Cw1155Deposits)>,
+    pub next_start_after: Option<(String, String)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Cw1155DepositsByOwnerResponse {
+    pub deposits: Vec<((String, String), Cw1155Deposits)>,
+    pub next_start_after: Option<(String, String)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct OffersResponse {
+    pub bids: Vec<(String, Offer)>,
+    pub next_start_after: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ListingsResponse {
+    pub listings: Vec<(String, Listing)>,
+    pub next_start_after: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AuctionResponse {
+    pub auction: Auction,
+    //lowest bid accepted at the current block
+    pub min_bid: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AuctionsResponse {
+    pub auctions: Vec<(String, Auction)>,
+    pub next_start_after: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AuctionBidsResponse {
+    pub bids: Vec<(u64, AuctionBid)>,
+    pub next_start_after: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TotalsResponse {
+    pub totals: Vec<(String, AssetTotal)>,
+    pub next_start_after: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetKind {
+    Native,
+    Cw20,
+    Cw721,
+    Cw1155,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PendingRewardsResponse {
+    pub rewards: Vec<(RewardToken, Uint128)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RewardPoolsResponse {
+    pub pools: Vec<(String, RewardPool)>,
+    pub next_start_after: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ConfigResponse {
+    pub admin: String,
+    pub pending_admin: Option<String>,
+    pub default_lock_duration: Duration,
+    pub open: bool,
+    pub early_withdraw: Option<EarlyWithdraw>,
+    pub unbonding_period: Option<Duration>,
+    pub marketplace_fee: Option<MarketplaceFee>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AllowedTokensResponse {
+    pub tokens: Vec<AllowedToken>,
+    pub next_start_after: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct LockScheduleResponse {
+    pub default_lock_duration: Duration,
+    pub cw20_lock_durations: Vec<Cw20LockDuration>,
+    pub next_start_after: Option<String>,
+}
+
+//options only apply to state that the migrated version did not have yet
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct MigrateMsg {
+    //required when migrating from v0.1.0, which stored no admin
+    pub admin: Option<String>,
+    pub default_lock_duration: Option<Duration>,
+    pub open: Option<bool>,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Cw20HookMsg {
     Deposit { },
+    //adds the sent tokens to the cw20 reward pool of staked_token
+    FundRewards { staked_token: String },
+    //adds the sent tokens to the cw20 reward pool of collection
+    FundNftRewards { collection: String },
+    //buys a listing priced in the sent cw20, the amount sent must be the price
+    Buy { contract_addr: String, token_id: String },
+    //bids the sent cw20 in an auction priced in it
+    BidAuction { contract_addr: String, token_id: String },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Cw721HookMsg {
     Deposit { }
-}
\ No newline at end of file
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(untagged)]
+pub enum ReceiveMsg {
+    Cw20(Cw20ReceiveMsg),
+    Cw1155(Cw1155ReceiveMsg),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw1155HookMsg {
+    Deposit { }
+}
@@ -1,39 +1,224 @@
 use std::marker::PhantomData;
 
-use cw20::Expiration;
+use cw_utils::{Duration, Expiration};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Uint128, Addr, Coin, BlockInfo, CustomMsg};
-use cw_storage_plus::{Map, Item, SnapshotItem, IndexedSnapshotMap, SnapshotMap, Strategy, UniqueIndex, Index, IndexList, MultiIndex, IndexedMap};
+use cosmwasm_std::{Uint128, Addr, Coin, CustomMsg, Decimal, Decimal256, Empty};
+use cw_storage_plus::{Map, Item, SnapshotItem, SnapshotMap, IndexedMap, IndexedSnapshotMap, Strategy, Index, IndexList, MultiIndex};
 
 pub struct Deposit<'a, C>
 where
     C: CustomMsg
 {
+    pub config: Item<'a, Config>,
+    //key is cw20 contract address, overrides config.default_lock_duration
+    pub cw20_lock_durations: Map<'a, &'a str, Duration>,
+    //key is token contract address
+    pub allowed_tokens: Map<'a, &'a str, TokenKind>,
+
+    //running totals keyed by denom, cw20 contract, cw721 collection and cw1155 contract/token_id
+    pub native_totals: Map<'a, &'a str, AssetTotal>,
+    pub cw20_totals: Map<'a, &'a str, AssetTotal>,
+    pub cw721_totals: Map<'a, &'a str, AssetTotal>,
+    pub cw1155_totals: Map<'a, &'a str, AssetTotal>,
+
     //keys address and denom
-    pub total_deposits: Item<'a, u64>,
-    pub deposits: Map<'a, (&'a str, &'a str), Deposits>,
+    pub deposits: SnapshotMap<'a, (&'a str, &'a str), Deposits>,
 
     pub total_cw20_deposits: SnapshotItem<'a, u64>,
-    pub cw20_deposits: IndexedMap<'a, (&'a str, &'a str), Cw20Deposits, Cw20DepositIndexes<'a>>,
+    pub cw20_deposits: IndexedSnapshotMap<'a, (&'a str, &'a str), Cw20Deposits, Cw20DepositIndexes<'a>>,
+    //key is address, cw20 contract address, lot id
+    pub cw20_lots: Map<'a, (&'a str, &'a str, u64), Cw20Lot>,
+    pub cw20_lot_seq: Item<'a, u64>,
+    //key is address, claim id
+    pub cw20_claims: Map<'a, (&'a str, u64), Cw20Claim>,
+    pub cw20_claim_seq: Item<'a, u64>,
+    //key is staked cw20 contract, reward denom or cw20 contract
+    pub reward_pools: Map<'a, (&'a str, &'a str), RewardPool>,
+    //key is staked cw20 contract, reward denom or cw20 contract, staker
+    pub reward_stakes: Map<'a, (&'a str, &'a str, &'a str), RewardStake>,
+    //key is cw721 collection, reward denom or cw20 contract
+    pub nft_reward_pools: Map<'a, (&'a str, &'a str), RewardPool>,
+    //key is cw721 collection, token_id, reward denom or cw20 contract
+    pub nft_reward_stakes: Map<'a, (&'a str, &'a str, &'a str), RewardStake>,
     //key is contract address, token_id
     pub cw721_deposits: IndexedSnapshotMap<'a, (&'a str, &'a str), Cw721Deposits, Cw721DepositIndexes<'a>>,
+    //key is owner, contract address, token_id. kept once the nft leaves so
+    //past custody of an owner can be looked up without walking the changelog
+    pub cw721_custody: Map<'a, (&'a str, &'a str, &'a str), Empty>,
+    //key is contract address, token_id, owner
+    pub cw1155_deposits: IndexedMap<'a, (&'a str, &'a str, &'a str), Cw1155Deposits, Cw1155DepositIndexes<'a>>,
+    //key is cw721 contract, token_id
+    pub listings: Map<'a, (&'a str, &'a str), Listing>,
+    //key is cw721 contract, token_id. removed once settled or cancelled
+    pub auctions: Map<'a, (&'a str, &'a str), Auction>,
+    pub auction_seq: Item<'a, u64>,
+    //key is auction id, bid number
+    pub auction_bids: Map<'a, (u64, u64), AuctionBid>,
+    //key is cw721 contract, token_id, bidder
+    pub offers: Map<'a, (&'a str, &'a str, &'a str), Offer>,
+    //key is owner, spender, AssetInfo::key
+    pub allowances: Map<'a, (&'a str, &'a str, &'a str), Allowance>,
+    //key is owner, operator
+    pub cw721_operators: Map<'a, (&'a str, &'a str), Expiration>,
     pub(crate) _custom_response: PhantomData<C>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub admin: Addr,
+    //set by ProposeNewAdmin until the proposed address accepts
+    pub pending_admin: Option<Addr>,
+    pub default_lock_duration: Duration,
+    //when open any cw20, cw721 or cw1155 can be deposited, otherwise only allowed_tokens
+    pub open: bool,
+    //None keeps EmergencyWithdrawCw20 disabled
+    #[serde(default)]
+    pub early_withdraw: Option<EarlyWithdraw>,
+    //when set cw20 leaves through UnbondCw20 and ClaimUnbonded instead of Withdraw
+    #[serde(default)]
+    pub unbonding_period: Option<Duration>,
+    //None keeps nft sales free of fees
+    #[serde(default)]
+    pub marketplace_fee: Option<MarketplaceFee>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct EarlyWithdraw {
+    //share of a lot withdrawn right after its deposit, falls linearly to zero at expiry
+    pub max_penalty: Decimal,
+    pub recipient: PenaltyRecipient,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct MarketplaceFee {
+    //share of every nft sale paid to collector instead of the seller
+    pub rate: Decimal,
+    pub collector: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PenaltyRecipient {
+    Treasury { address: String },
+    //paid out like rewards to whoever is still staked in the same cw20, or to the admin if nobody is
+    Stakers {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenKind {
+    Cw20,
+    Cw721,
+    Cw1155,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardToken {
+    Native { denom: String },
+    Cw20 { contract: String },
+}
+
+impl RewardToken {
+    //second part of the reward_pools key
+    pub fn key(&self) -> &str {
+        match self {
+            RewardToken::Native { denom } => denom,
+            RewardToken::Cw20 { contract } => contract,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardPool {
+    pub reward: RewardToken,
+    //split across all staked cw20, paid in full to every escrowed nft
+    pub reward_per_block: Uint128,
+    //funded but not emitted yet
+    pub balance: Uint128,
+    //emitted rewards per staked token since the pool was created
+    pub reward_per_share: Decimal256,
+    pub last_update_height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct RewardStake {
+    //reward_per_share when the staker was last settled
+    pub reward_per_share_paid: Decimal256,
+    pub pending: Uint128,
+}
+
+//amount is the number of tokens for cw721, depositors the owners with a non zero balance
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct AssetTotal {
+    pub amount: Uint128,
+    pub depositors: u64,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Cw20Deposits {
     pub count: u64,
     pub owner: String,
     pub contract:String,
     pub amount:Uint128,
+    //unlock time of the most recent lot, each lot in cw20_lots unlocks on its own
     pub stake_time:Expiration
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw20Lot {
+    pub amount:Uint128,
+    pub stake_time:Expiration,
+    //lock the lot was deposited with, scales the early withdraw penalty
+    pub lock_duration:Duration
+}
+
+//any asset the contract holds deposits of, a cw721 token is an amount of one
+//while every cw1155 token_id is a balance of its own
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetInfo {
+    Native { denom: String },
+    Cw20 { contract: String },
+    Cw721 { contract: String, token_id: String },
+    Cw1155 { contract: String, token_id: String },
+}
+
+impl AssetInfo {
+    //last part of the allowances key, also keys cw1155_totals
+    pub fn key(&self) -> String {
+        match self {
+            AssetInfo::Native { denom } => denom.clone(),
+            AssetInfo::Cw20 { contract } => contract.clone(),
+            AssetInfo::Cw721 { contract, token_id } | AssetInfo::Cw1155 { contract, token_id } => {
+                format!("{}/{}", contract, token_id)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Allowance {
+    pub asset: AssetInfo,
+    pub amount: Uint128,
+    pub expires: Expiration,
+}
+
+//unbonded cw20 waiting for release_at, it no longer counts as deposited
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw20Claim {
+    pub contract: String,
+    pub amount: Uint128,
+    pub release_at: Expiration,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Deposits {
-    pub count: i32,
+    pub count: u64,
     pub owner: Addr,
     pub coins: Coin
 }
@@ -45,9 +230,69 @@ pub struct Cw721Deposits {
     pub token_id:String
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw1155Deposits {
+    pub owner: String,
+    pub contract:String,
+    pub token_id:String,
+    pub amount:Uint128
+}
+
+//native coins locked by a bidder until the bid is accepted or cancelled
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Offer {
+    pub token_id: String,
+    pub amount: Coin,
+}
+
+//fixed price sale of an escrowed nft, payment is a native denom or a cw20.
+//dropped whenever the nft leaves the seller
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Listing {
+    pub seller: String,
+    pub contract: String,
+    pub token_id: String,
+    pub payment: AssetInfo,
+    pub price: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuctionKind {
+    //each bid has to beat the last by min_increment, a bid within extension
+    //of the end moves the end to extension after that bid
+    English { min_increment: Uint128, extension: Duration },
+    //the price falls from start_price to end_price, the first bid at the price wins
+    Dutch { end_price: Uint128 },
+}
+
+//timed sale of an escrowed nft, the nft stays with the seller until settled.
+//the highest bid is held by the contract and refunded once outbid
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Auction {
+    pub id: u64,
+    pub seller: String,
+    pub contract: String,
+    pub token_id: String,
+    pub payment: AssetInfo,
+    pub kind: AuctionKind,
+    pub start_price: Uint128,
+    pub start: Expiration,
+    pub end: Expiration,
+    pub highest_bid: Option<AuctionBid>,
+    pub bid_count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuctionBid {
+    pub bidder: String,
+    pub amount: Uint128,
+    pub height: u64,
+}
+
 pub struct Cw20DepositIndexes<'a> {
-    pub count: MultiIndex<'a, u64, Cw20Deposits, &'a str>,
-    pub owner: MultiIndex<'a, String, Cw20Deposits, &'a str>,
+    pub count: MultiIndex<'a, u64, Cw20Deposits, (&'a str, &'a str)>,
+    pub owner: MultiIndex<'a, String, Cw20Deposits, (&'a str, &'a str)>,
 }
 
 impl<'a> IndexList<Cw20Deposits> for Cw20DepositIndexes<'a> {
@@ -58,7 +303,7 @@ impl<'a> IndexList<Cw20Deposits> for Cw20DepositIndexes<'a> {
 }
 
 pub struct Cw721DepositIndexes<'a> {
-    pub owner: MultiIndex<'a, String, Cw721Deposits, &'a str>,
+    pub owner: MultiIndex<'a, String, Cw721Deposits, (&'a str, &'a str)>,
 }
 
 impl<'a> IndexList<Cw721Deposits> for Cw721DepositIndexes<'a> {
@@ -68,13 +313,23 @@ impl<'a> IndexList<Cw721Deposits> for Cw721DepositIndexes<'a> {
     }
 }
 
+pub struct Cw1155DepositIndexes<'a> {
+    pub owner: MultiIndex<'a, String, Cw1155Deposits, (&'a str, &'a str, &'a str)>,
+}
+
+impl<'a> IndexList<Cw1155Deposits> for Cw1155DepositIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Cw1155Deposits>> + '_> {
+        let v: Vec<&dyn Index<Cw1155Deposits>> = vec![&self.owner];
+        Box::new(v.into_iter())
+    }
+}
+
 impl<C> Default for Deposit<'static, C>
 where
     C: CustomMsg
 {
     fn default() -> Self {
         Self::new(
-            "total_deposits",
             "deposits",
         )
     }
@@ -85,34 +340,69 @@ where
     C: CustomMsg
 {
     fn new(
-        total_deposits_key: &'a str,
         deposits_key: &'a str,
     ) -> Self {
         Self {
-            total_deposits: Item::new(total_deposits_key),
-            deposits: Map::new(deposits_key),
+            config: Item::new("config"),
+            cw20_lock_durations: Map::new("cw20_lock_durations"),
+            allowed_tokens: Map::new("allowed_tokens"),
+            native_totals: Map::new("native_totals"),
+            cw20_totals: Map::new("cw20_totals"),
+            cw721_totals: Map::new("cw721_totals"),
+            cw1155_totals: Map::new("cw1155_totals"),
+            deposits: SnapshotMap::new(
+                deposits_key,
+                "deposits_check",
+                "deposits_change",
+                Strategy::EveryBlock
+            ),
             total_cw20_deposits: SnapshotItem::new(
                 "total_cw20_deposits",
                 "total_cw20_deposits_check",
                 "total_cw20_deposits_change",
                 Strategy::EveryBlock,
             ),
-            cw20_deposits: IndexedMap::new(
+            cw20_deposits: IndexedSnapshotMap::new(
                 "cw20_deposits",
+                "cw20_deposits_check",
+                "cw20_deposits_change",
+                Strategy::EveryBlock,
                 Cw20DepositIndexes {
-                    count: MultiIndex::new(|_pk, d| d.count.clone(), "cw20deposits", "cw20deposits__count"),
-                    owner: MultiIndex::new(|_pk, d| d.owner.clone(), "cw20deposits", "cw20deposits__owner")
+                    count: MultiIndex::new(|_pk, d| d.count, "cw20_deposits", "cw20deposits__count"),
+                    owner: MultiIndex::new(|_pk, d| d.owner.clone(), "cw20_deposits", "cw20deposits__owner")
                 },
             ),
+            cw20_lots: Map::new("cw20_lots"),
+            cw20_lot_seq: Item::new("cw20_lot_seq"),
+            cw20_claims: Map::new("cw20_claims"),
+            cw20_claim_seq: Item::new("cw20_claim_seq"),
+            reward_pools: Map::new("reward_pools"),
+            reward_stakes: Map::new("reward_stakes"),
+            nft_reward_pools: Map::new("nft_reward_pools"),
+            nft_reward_stakes: Map::new("nft_reward_stakes"),
             cw721_deposits: IndexedSnapshotMap::new(
                 "cw721_deposits",
                 "cw721_deposits_check",
                 "cw721_deposits_change",
                 Strategy::EveryBlock,
                 Cw721DepositIndexes { 
-                    owner: MultiIndex::new(|_pk, d| d.owner.clone(), "cw721deposits", "cw721deposits__owner")
+                    owner: MultiIndex::new(|_pk, d| d.owner.clone(), "cw721_deposits", "cw721deposits__owner")
+                }
+            ),
+            cw721_custody: Map::new("cw721_custody"),
+            cw721_operators: Map::new("cw721_operators"),
+            offers: Map::new("offers"),
+            listings: Map::new("listings"),
+            auctions: Map::new("auctions"),
+            auction_seq: Item::new("auction_seq"),
+            auction_bids: Map::new("auction_bids"),
+            cw1155_deposits: IndexedMap::new(
+                "cw1155_deposits",
+                Cw1155DepositIndexes {
+                    owner: MultiIndex::new(|_pk, d| d.owner.clone(), "cw1155_deposits", "cw1155deposits__owner")
                 }
             ),
+            allowances: Map::new("allowances"),
             _custom_response: PhantomData,
         }
     }
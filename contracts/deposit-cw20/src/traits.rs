@@ -1,6 +1,18 @@
-use cosmwasm_std::{DepsMut, MessageInfo, Response, StdResult, Deps, CustomMsg, Env, Uint128};
+use cosmwasm_std::{
+    Binary, CustomMsg, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint128,
+};
+use cw_utils::{Duration, Expiration};
 
-use crate::msg::{DepositResponse, Cw20DepositResponse, Cw721DepositResponse};
+use crate::msg::{
+    AllowancesResponse, AllowedTokensResponse, AssetKind, AuctionBidsResponse, AuctionResponse,
+    AuctionsResponse, BalanceResponse, ClaimsResponse, ConfigResponse, Cw1155DepositResponse,
+    Cw1155DepositsByOwnerResponse, Cw20DepositResponse, Cw20LotsResponse, Cw721DepositAtResponse,
+    Cw721DepositChangelogResponse, Cw721DepositResponse, Cw721DepositsByOwnerResponse,
+    Cw721OperatorsResponse, DepositResponse, ListingsResponse, LockScheduleResponse,
+    OffersResponse, PendingRewardsResponse, RewardPoolsResponse,
+    TotalCw20DepositsChangelogResponse, TotalCw20DepositsResponse, TotalsResponse,
+};
+use crate::state::{AssetInfo, AuctionKind, EarlyWithdraw, MarketplaceFee, RewardToken, TokenKind};
 
 pub trait Deposit<C>: DepositExecute<C> + DepositQuery
 where
@@ -13,18 +25,554 @@ where
     C: CustomMsg,
 {
     type Err: ToString;
-    fn execute_deposit(&self, deps: DepsMut, info: MessageInfo) -> Result<Response<C>, Self::Err>;
-    fn execute_withdraw(&self, deps: DepsMut, info: MessageInfo, amount:u128, denom:String) -> Result<Response<C>, Self::Err>;
-    fn execute_cw20_deposit(&self, deps: DepsMut, env:Env, info: MessageInfo, owner:String, amount:Uint128) -> Result<Response<C>, Self::Err>;
-    fn execute_cw20_withdraw(&self, deps: DepsMut, env: Env, info: MessageInfo, contract:String, amount: Uint128) -> Result<Response<C>, Self::Err>;
-    fn execute_cw721_deposit(&self, deps: DepsMut, env:Env, info: MessageInfo, owner:String, token_id:String) -> Result<Response<C>, Self::Err>;
-    fn execute_cw721_withdraw(&self, deps: DepsMut, env:Env, info: MessageInfo, contract:String, token_id: String) -> Result<Response<C>, Self::Err>;
+    fn execute_deposit(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response<C>, Self::Err>;
+    #[allow(clippy::too_many_arguments)]
+    fn execute_withdraw(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        asset: AssetInfo,
+        amount: Uint128,
+        recipient: Option<String>,
+        msg: Option<Binary>,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_cw20_deposit(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        owner: String,
+        amount: Uint128,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_cw20_emergency_withdraw(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract: String,
+        amount: Uint128,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_transfer_deposit(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        recipient: String,
+        asset: AssetInfo,
+        amount: Uint128,
+    ) -> Result<Response<C>, Self::Err>;
+    #[allow(clippy::too_many_arguments)]
+    fn execute_increase_allowance(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        spender: String,
+        asset: AssetInfo,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    ) -> Result<Response<C>, Self::Err>;
+    #[allow(clippy::too_many_arguments)]
+    fn execute_decrease_allowance(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        spender: String,
+        asset: AssetInfo,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    ) -> Result<Response<C>, Self::Err>;
+    #[allow(clippy::too_many_arguments)]
+    fn execute_withdraw_from(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        owner: String,
+        asset: AssetInfo,
+        amount: Uint128,
+        recipient: String,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_cw20_unbond(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract: String,
+        amount: Uint128,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_claim_unbonded(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_cw721_deposit(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        owner: String,
+        token_id: String,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_cw721_approve_operator(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        operator: String,
+        expires: Option<Expiration>,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_cw721_revoke_operator(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        operator: String,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_cw1155_deposit(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        owner: String,
+        batch: Vec<(String, Uint128)>,
+    ) -> Result<Response<C>, Self::Err>;
+    #[allow(clippy::too_many_arguments)]
+    fn execute_cw1155_batch_withdraw(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract_addr: String,
+        batch: Vec<(String, Uint128)>,
+        recipient: Option<String>,
+        msg: Option<Binary>,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_update_config(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        default_lock_duration: Option<Duration>,
+        open: Option<bool>,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_update_early_withdraw(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        early_withdraw: Option<EarlyWithdraw>,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_update_unbonding_period(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        unbonding_period: Option<Duration>,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_update_marketplace_fee(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        marketplace_fee: Option<MarketplaceFee>,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_propose_new_admin(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        new_admin: String,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_accept_admin(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_add_allowed_token(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        contract: String,
+        kind: TokenKind,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_remove_allowed_token(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        contract: String,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_update_cw20_lock_duration(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        contract: String,
+        duration: Option<Duration>,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_set_reward_pool(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        staked_token: String,
+        reward: RewardToken,
+        reward_per_block: Uint128,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_fund_rewards(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        staked_token: String,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_cw20_fund_rewards(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        staked_token: String,
+        amount: Uint128,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_set_nft_reward_pool(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        collection: String,
+        reward: RewardToken,
+        reward_per_block: Uint128,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_fund_nft_rewards(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        collection: String,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_cw20_fund_nft_rewards(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        collection: String,
+        amount: Uint128,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_claim_nft_rewards(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_claim_rewards(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        staked_token: String,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_place_bid(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        contract_addr: String,
+        token_id: String,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_cancel_bid(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        contract_addr: String,
+        token_id: String,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_accept_bid(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract_addr: String,
+        token_id: String,
+        bidder: String,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_list_nft(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        contract_addr: String,
+        token_id: String,
+        payment: AssetInfo,
+        price: Uint128,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_delist_nft(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        contract_addr: String,
+        token_id: String,
+    ) -> Result<Response<C>, Self::Err>;
+    #[allow(clippy::too_many_arguments)]
+    fn execute_buy_nft(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        buyer: String,
+        contract_addr: String,
+        token_id: String,
+        payment: AssetInfo,
+        amount: Uint128,
+    ) -> Result<Response<C>, Self::Err>;
+    #[allow(clippy::too_many_arguments)]
+    fn execute_start_auction(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract_addr: String,
+        token_id: String,
+        payment: AssetInfo,
+        kind: AuctionKind,
+        start_price: Uint128,
+        duration: Duration,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_cancel_auction(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        contract_addr: String,
+        token_id: String,
+    ) -> Result<Response<C>, Self::Err>;
+    #[allow(clippy::too_many_arguments)]
+    fn execute_bid_auction(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        bidder: String,
+        contract_addr: String,
+        token_id: String,
+        payment: AssetInfo,
+        amount: Uint128,
+    ) -> Result<Response<C>, Self::Err>;
+    fn execute_settle_auction(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        contract_addr: String,
+        token_id: String,
+    ) -> Result<Response<C>, Self::Err>;
 }
 
 pub trait DepositQuery {
-    fn query_deposits(&self, deps: Deps, address: String) -> StdResult<DepositResponse>;
-    fn query_cw20_deposits(&self, deps: Deps, address: String) -> StdResult<Cw20DepositResponse>;
-    fn query_cw721_by_contract(&self, deps: Deps, contract_addr:String) -> StdResult<Cw721DepositResponse>;
-    fn query_cw721_by_owner(&self, deps: Deps, address: String) -> StdResult<Cw721DepositResponse>;
-    fn query_total_cw20_deposits_changelog(&self, deps: Deps) -> StdResult<Vec<(u64, Option<u64>)>>;
-}
\ No newline at end of file
+    fn query_deposits(
+        &self,
+        deps: Deps,
+        address: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<DepositResponse>;
+    fn query_deposits_at(
+        &self,
+        deps: Deps,
+        address: String,
+        height: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<DepositResponse>;
+    fn query_cw20_deposits_at(
+        &self,
+        deps: Deps,
+        address: String,
+        height: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Cw20DepositResponse>;
+    fn query_cw20_deposits(
+        &self,
+        deps: Deps,
+        address: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Cw20DepositResponse>;
+    fn query_cw721_by_contract(
+        &self,
+        deps: Deps,
+        contract_addr: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Cw721DepositResponse>;
+    fn query_cw721_by_owner(
+        &self,
+        deps: Deps,
+        address: String,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    ) -> StdResult<Cw721DepositsByOwnerResponse>;
+    fn query_cw721_deposit_changelog(
+        &self,
+        deps: Deps,
+        contract_addr: String,
+        token_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<Cw721DepositChangelogResponse>;
+    fn query_cw721_deposit_at(
+        &self,
+        deps: Deps,
+        contract_addr: String,
+        token_id: String,
+        height: u64,
+    ) -> StdResult<Cw721DepositAtResponse>;
+    fn query_cw721_by_owner_at(
+        &self,
+        deps: Deps,
+        address: String,
+        height: u64,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    ) -> StdResult<Cw721DepositsByOwnerResponse>;
+    fn query_cw721_operators(
+        &self,
+        deps: Deps,
+        address: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Cw721OperatorsResponse>;
+    fn query_cw1155_by_contract(
+        &self,
+        deps: Deps,
+        contract_addr: String,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    ) -> StdResult<Cw1155DepositResponse>;
+    fn query_cw1155_by_owner(
+        &self,
+        deps: Deps,
+        address: String,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    ) -> StdResult<Cw1155DepositsByOwnerResponse>;
+    fn query_offers(
+        &self,
+        deps: Deps,
+        contract_addr: String,
+        token_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<OffersResponse>;
+    fn query_listings(
+        &self,
+        deps: Deps,
+        collection: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<ListingsResponse>;
+    fn query_auction(
+        &self,
+        deps: Deps,
+        env: Env,
+        contract_addr: String,
+        token_id: String,
+    ) -> StdResult<AuctionResponse>;
+    fn query_auctions(
+        &self,
+        deps: Deps,
+        collection: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<AuctionsResponse>;
+    fn query_auction_bids(
+        &self,
+        deps: Deps,
+        auction_id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<AuctionBidsResponse>;
+    fn query_balance(
+        &self,
+        deps: Deps,
+        address: String,
+        asset: AssetInfo,
+    ) -> StdResult<BalanceResponse>;
+    fn query_allowances(
+        &self,
+        deps: Deps,
+        owner: String,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    ) -> StdResult<AllowancesResponse>;
+    fn query_claims(
+        &self,
+        deps: Deps,
+        address: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<ClaimsResponse>;
+    fn query_cw20_lots(
+        &self,
+        deps: Deps,
+        env: Env,
+        address: String,
+        contract: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<Cw20LotsResponse>;
+    fn query_config(&self, deps: Deps) -> StdResult<ConfigResponse>;
+    fn query_totals(
+        &self,
+        deps: Deps,
+        kind: AssetKind,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TotalsResponse>;
+    fn query_pending_rewards(
+        &self,
+        deps: Deps,
+        env: Env,
+        address: String,
+        staked_token: String,
+    ) -> StdResult<PendingRewardsResponse>;
+    fn query_pending_nft_rewards(
+        &self,
+        deps: Deps,
+        env: Env,
+        owner: String,
+    ) -> StdResult<PendingRewardsResponse>;
+    fn query_nft_reward_pools(
+        &self,
+        deps: Deps,
+        collection: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<RewardPoolsResponse>;
+    fn query_reward_pools(
+        &self,
+        deps: Deps,
+        staked_token: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<RewardPoolsResponse>;
+    fn query_allowed_tokens(
+        &self,
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<AllowedTokensResponse>;
+    fn query_lock_schedule(
+        &self,
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<LockScheduleResponse>;
+    fn query_total_cw20_deposits(
+        &self,
+        deps: Deps,
+        env: Env,
+        height: Option<u64>,
+    ) -> StdResult<TotalCw20DepositsResponse>;
+    fn query_total_cw20_deposits_changelog(
+        &self,
+        deps: Deps,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<TotalCw20DepositsChangelogResponse>;
+}